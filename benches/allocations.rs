@@ -0,0 +1,70 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use ruby::LinkedList;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Counts every call into the allocator so that we can report how many allocations a single list
+// operation costs with and without the recycling pool.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const OPERATIONS: usize = 10_000;
+
+fn churn(list: &LinkedList<usize>) {
+    std::thread::scope(|s| {
+        for t in 0..4 {
+            s.spawn(move || {
+                for i in 0..OPERATIONS / 8 {
+                    list.insert_from_head(t * OPERATIONS + i).unwrap();
+                }
+            });
+            s.spawn(move || {
+                for _ in 0..OPERATIONS / 8 {
+                    let _ = list.delete_from_tail();
+                }
+            });
+        }
+    });
+    while list.delete_from_tail().unwrap().is_some() {}
+}
+
+fn allocations_per_operation(list: &LinkedList<usize>) -> f64 {
+    // one round to warm up the hazard pointers and, for the pooled list, the pool itself
+    churn(list);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    churn(list);
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+    (after - before) as f64 / OPERATIONS as f64
+}
+
+fn benchmark(c: &mut Criterion) {
+    println!(
+        "allocations per operation: boxed {:.3}, pooled {:.3}",
+        allocations_per_operation(&LinkedList::new()),
+        allocations_per_operation(&LinkedList::with_pool()),
+    );
+    let mut group = c.benchmark_group("Allocations");
+    let boxed = LinkedList::new();
+    let pooled = LinkedList::with_pool();
+    group.bench_function("Boxed", |b| b.iter(|| churn(&boxed)));
+    group.bench_function("Pooled", |b| b.iter(|| churn(&pooled)));
+    group.finish();
+}
+
+criterion_group! {name = benchmarks; config = Criterion::default(); targets = benchmark}
+criterion_main!(benchmarks);
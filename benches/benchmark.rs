@@ -1,5 +1,5 @@
-use criterion::{Criterion, criterion_group, criterion_main};
-use ruby::LinkedList;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use ruby::{Backoff, CombiningList, Exponential, LinkedList, NoBackoff, Spin};
 use std::collections::LinkedList as StdLinkedList;
 use std::sync::Mutex;
 
@@ -24,23 +24,142 @@ fn ruby() {
     std::thread::scope(|s| {
         for i in 0..10 {
             s.spawn(move || {
-                new.insert_from_head(i);
+                new.insert_from_head(i).unwrap();
             });
         }
         for _ in 0..10 {
             s.spawn(move || {
-                new.delete_from_tail();
+                let _ = new.delete_from_tail();
             });
         }
     });
 }
 
+// Every producer pushes and every consumer pops the same number of elements, so the work per
+// thread stays fixed and the time per iteration shows how well the list scales with the thread
+// count. Run with --save-baseline before and --baseline after a layout change to compare.
+fn producers_consumers(pairs: usize) {
+    producers_consumers_on(&LinkedList::new(), pairs);
+}
+
+fn producers_consumers_on(new: &LinkedList<usize>, pairs: usize) {
+    std::thread::scope(|s| {
+        for _ in 0..pairs {
+            s.spawn(move || {
+                for i in 0..1000 {
+                    new.insert_from_head(i).unwrap();
+                }
+            });
+            s.spawn(move || {
+                for _ in 0..1000 {
+                    let _ = new.delete_from_tail();
+                }
+            });
+        }
+    });
+}
+
+fn scalability(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Scalability");
+    for pairs in [1, 2, 4, 8] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(pairs * 2),
+            &pairs,
+            |b, &pairs| b.iter(|| producers_consumers(pairs)),
+        );
+    }
+    group.finish();
+}
+
+// Producers at the head and consumers at the tail of a list that is kept a thousand elements deep,
+// so the two sides never meet, next to the same workload on a list that starts out empty and keeps
+// running into its last node, where inserts and deletes have to take turns.
+fn throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Throughput");
+    for pairs in [1, 2, 4] {
+        group.throughput(Throughput::Elements((pairs * 2 * 1000) as u64));
+        group.bench_with_input(BenchmarkId::new("Empty", pairs * 2), &pairs, |b, &pairs| {
+            b.iter(|| producers_consumers(pairs))
+        });
+        let filled = LinkedList::new();
+        for i in 0..1000 {
+            filled.insert_from_head(i).unwrap();
+        }
+        group.bench_with_input(
+            BenchmarkId::new("Filled", pairs * 2),
+            &pairs,
+            |b, &pairs| b.iter(|| producers_consumers_on(&filled, pairs)),
+        );
+    }
+    group.finish();
+}
+
+// The same producer and consumer workload run against the flat combining front-end, to put next to
+// LinkedList with the descriptor protocol at the same thread counts.
+fn combining_producers_consumers(pairs: usize) {
+    let new = &CombiningList::new();
+    std::thread::scope(|s| {
+        for _ in 0..pairs {
+            s.spawn(move || {
+                for i in 0..1000 {
+                    new.insert_from_head(i).unwrap();
+                }
+            });
+            s.spawn(move || {
+                for _ in 0..1000 {
+                    let _ = new.delete_from_tail();
+                }
+            });
+        }
+    });
+}
+
+fn combining(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Combining");
+    for threads in [2, 8, 32] {
+        group.bench_with_input(
+            BenchmarkId::new("Descriptors", threads),
+            &threads,
+            |b, &threads| b.iter(|| producers_consumers(threads / 2)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Combining", threads),
+            &threads,
+            |b, &threads| b.iter(|| combining_producers_consumers(threads / 2)),
+        );
+    }
+    group.finish();
+}
+
+static NO_BACKOFF: NoBackoff = NoBackoff::new();
+static SPIN: Spin = Spin::new();
+static EXPONENTIAL: Exponential = Exponential::new();
+
+fn backoff(c: &mut Criterion) {
+    let strategies: [(&str, &'static dyn Backoff); 3] = [
+        ("None", &NO_BACKOFF),
+        ("Spin", &SPIN),
+        ("Exponential", &EXPONENTIAL),
+    ];
+    let mut group = c.benchmark_group("Backoff");
+    for threads in [2, 8, 32] {
+        for (name, strategy) in strategies {
+            group.bench_with_input(BenchmarkId::new(name, threads), &threads, |b, &threads| {
+                b.iter(|| {
+                    producers_consumers_on(&LinkedList::new().with_backoff(strategy), threads / 2)
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
 fn benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("Bravo");
-    group.bench_function("Std", |b| b.iter(|| std_mutex_list()));
-    group.bench_function("Ruby", |b| b.iter(|| ruby()));
+    group.bench_function("Std", |b| b.iter(std_mutex_list));
+    group.bench_function("Ruby", |b| b.iter(ruby));
     group.finish();
 }
 
-criterion_group! {name = benchmarks; config = Criterion::default(); targets = benchmark}
+criterion_group! {name = benchmarks; config = Criterion::default(); targets = benchmark, scalability, throughput, backoff, combining}
 criterion_main!(benchmarks);
@@ -0,0 +1,245 @@
+use crate::descriptor::Inserted;
+use crate::sync::CachePadded;
+use crate::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, fence};
+use crate::{LinkedList, Node};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::sync::atomic::Ordering;
+
+// Number of operations that can be announced at the same time. As with the combining list a
+// thread that finds every slot taken waits for one to come free, so the bounds only hold for up
+// to this many threads working on the same list at once.
+const SLOTS: usize = 64;
+
+const FREE: usize = 0;
+const CLAIMED: usize = 1;
+
+// An operation a thread has announced so that the others can finish it for it. The phase is
+// handed out from a counter that only ever grows, so it orders the announcements by age and tells
+// apart the operations that reuse the same slot. The operation is done once done has caught up
+// with its phase, and every step of a descriptor working for an announcement checks that first,
+// which is what keeps an operation from taking effect twice when several threads help it at once.
+pub(crate) struct Announcement<T> {
+    state: AtomicUsize,
+    pub(crate) phase: AtomicUsize,
+    pub(crate) done: AtomicUsize,
+    pub(crate) insert: AtomicBool,
+    // the node an insert links in, written before the phase is published
+    pub(crate) node: AtomicPtr<Node<T>>,
+    // set by the owner of an insert while it links its node into an empty list, which it does
+    // itself because a CAS from null can not tell a node that got linked and taken out again from
+    // one that never was
+    pub(crate) busy: AtomicBool,
+    // set before done catches up with the phase of an insert that found the list full, its node
+    // never got linked and the owner takes the value back out of it
+    rejected: AtomicBool,
+    // the value of a delete, written by the helper that moves it out of the node before the
+    // operation counts as done and only read by the owner afterwards
+    pub(crate) result: UnsafeCell<Option<T>>,
+}
+
+impl<T> Announcement<T> {
+    pub(crate) fn finished(&self, phase: usize) -> bool {
+        self.done.load(Ordering::SeqCst) >= phase
+    }
+
+    pub(crate) fn finish(&self, phase: usize) {
+        self.done.fetch_max(phase, Ordering::SeqCst);
+    }
+
+    pub(crate) fn reject(&self, phase: usize) {
+        self.rejected.store(true, Ordering::SeqCst);
+        self.finish(phase);
+    }
+}
+
+pub(crate) struct Announcements<T> {
+    phase: CachePadded<AtomicUsize>,
+    slots: Box<[CachePadded<Announcement<T>>]>,
+}
+
+unsafe impl<T> Send for Announcements<T> where T: Send {}
+unsafe impl<T> Sync for Announcements<T> where T: Send {}
+
+impl<T> Announcements<T> {
+    pub(crate) fn new() -> Self {
+        let slots: Vec<_> = (0..SLOTS)
+            .map(|_| {
+                CachePadded::new(Announcement {
+                    state: AtomicUsize::new(FREE),
+                    phase: AtomicUsize::new(0),
+                    done: AtomicUsize::new(0),
+                    insert: AtomicBool::new(false),
+                    node: AtomicPtr::new(core::ptr::null_mut()),
+                    busy: AtomicBool::new(false),
+                    rejected: AtomicBool::new(false),
+                    result: UnsafeCell::new(None),
+                })
+            })
+            .collect();
+        Self {
+            phase: CachePadded::new(AtomicUsize::new(0)),
+            slots: slots.into_boxed_slice(),
+        }
+    }
+}
+
+impl<T: 'static> LinkedList<T> {
+    /// Switches the list over to helping announced operations. Every `insert_from_head` and
+    /// `delete_from_tail` is announced together with a phase number before it touches the list,
+    /// and each thread first helps all announced operations that are older than its own to
+    /// finish, oldest first, before it gets to its own. One of them that keeps losing its CAS to
+    /// the others therefore gets finished by the threads that keep winning instead of starving.
+    ///
+    /// That is not a bound on the number of steps though. Linking the first node into an empty
+    /// list and waiting for room are left to the thread that announced the insert, a delete
+    /// waits for whichever helper moves its value into the announcement, and the operations that
+    /// are not announced, the batches, `take_all`, `delete_from_tail_if`, the removes and the
+    /// cancels, never help and can keep taking the descriptor slots away from the announced ones.
+    pub fn wait_free(mut self) -> Self {
+        self.announcements = Some(Box::new(Announcements::new()));
+        self
+    }
+
+    pub(crate) fn announced_insert(
+        &self,
+        announcements: &Announcements<T>,
+        node: *mut Node<T>,
+    ) -> Result<(), T> {
+        let (index, slot) = self.announce(announcements, true, node);
+        self.help_announced(announcements, index);
+        let ret = if slot.rejected.load(Ordering::SeqCst) {
+            Err(self.take_back(node))
+        } else {
+            Ok(())
+        };
+        slot.state.store(FREE, Ordering::SeqCst);
+        ret
+    }
+
+    pub(crate) fn announced_delete(&self, announcements: &Announcements<T>) -> Option<T> {
+        let (index, slot) = self.announce(announcements, false, core::ptr::null_mut());
+        self.help_announced(announcements, index);
+        // done has caught up with our phase so nobody writes to the result anymore
+        let ret = unsafe { (*slot.result.get()).take() };
+        slot.state.store(FREE, Ordering::SeqCst);
+        ret
+    }
+
+    fn announce<'a>(
+        &self,
+        announcements: &'a Announcements<T>,
+        insert: bool,
+        node: *mut Node<T>,
+    ) -> (usize, &'a Announcement<T>) {
+        let mut step = 0;
+        loop {
+            for (index, slot) in announcements.slots.iter().enumerate() {
+                if slot
+                    .state
+                    .compare_exchange(FREE, CLAIMED, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+                {
+                    slot.insert.store(insert, Ordering::SeqCst);
+                    slot.rejected.store(false, Ordering::SeqCst);
+                    slot.node.store(node, Ordering::SeqCst);
+                    let phase = announcements.phase.fetch_add(1, Ordering::SeqCst) + 1;
+                    slot.phase.store(phase, Ordering::SeqCst);
+                    return (index, slot);
+                }
+            }
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    // Finishes every announced operation up to and including the one in our own slot, in the
+    // order of their phases.
+    fn help_announced(&self, announcements: &Announcements<T>, own: usize) {
+        let own_phase = announcements.slots[own].phase.load(Ordering::SeqCst);
+        let mut floor = 0;
+        loop {
+            let mut oldest = None;
+            for (index, slot) in announcements.slots.iter().enumerate() {
+                let phase = slot.phase.load(Ordering::SeqCst);
+                if phase > floor
+                    && phase <= own_phase
+                    && !slot.finished(phase)
+                    && oldest.is_none_or(|(_, oldest_phase)| phase < oldest_phase)
+                {
+                    oldest = Some((index, phase));
+                }
+            }
+            let Some((index, phase)) = oldest else {
+                return;
+            };
+            let slot = &announcements.slots[index];
+            if slot.insert.load(Ordering::SeqCst) {
+                let node = slot.node.load(Ordering::SeqCst);
+                self.help_insert(slot, phase, node, index == own);
+            } else {
+                self.delete(Some((&**slot, phase)));
+            }
+            floor = phase;
+        }
+    }
+
+    fn help_insert(&self, slot: &Announcement<T>, phase: usize, node: *mut Node<T>, owner: bool) {
+        let mut step = 0;
+        while !slot.finished(phase) {
+            if slot.busy.load(Ordering::SeqCst) {
+                // the owner is linking the node into an empty list right now
+                return;
+            }
+            if self.head.load(Ordering::SeqCst).is_null() {
+                if !owner {
+                    return;
+                }
+                if self.capacity == 0 || self.closed.load(Ordering::SeqCst) {
+                    slot.reject(phase);
+                    return;
+                }
+                slot.busy.store(true, Ordering::SeqCst);
+                if !slot.finished(phase)
+                    && self
+                        .head
+                        .compare_exchange(
+                            core::ptr::null_mut(),
+                            node,
+                            Ordering::SeqCst,
+                            Ordering::SeqCst,
+                        )
+                        .is_ok()
+                {
+                    // same as the null path of link, a close we missed takes the node back out
+                    fence(Ordering::SeqCst);
+                    if self.closed.load(Ordering::SeqCst)
+                        && self
+                            .head
+                            .compare_exchange(
+                                node,
+                                core::ptr::null_mut(),
+                                Ordering::SeqCst,
+                                Ordering::SeqCst,
+                            )
+                            .is_ok()
+                    {
+                        slot.reject(phase);
+                    } else {
+                        // a swap for the same reason as in the null path of splice
+                        self.start_over(node);
+                        self.tail.swap(node, Ordering::SeqCst);
+                        slot.finish(phase);
+                    }
+                }
+                slot.busy.store(false, Ordering::SeqCst);
+                continue;
+            }
+            if let Inserted::Empty = self.insert(node, Some((slot, phase))) {
+                self.backoff.snooze(step);
+                step = step.saturating_add(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::sync;
+
+// Exponential spins 2^step times up to this step and yields the thread from then on.
+const SPIN_LIMIT: u32 = 6;
+// Number of spin hints per round for the fixed spin strategy.
+const SPINS: u32 = 1 << 4;
+
+/// Decides what a thread does whenever one of the retry loops of a list has to go around again,
+/// be it because a CAS failed or because another operation was still pending and had to be helped
+/// first. `step` counts the rounds the current operation has gone around so far, starting at zero
+/// and sticking at `u32::MAX` for a loop that never gives up, which lets the strategies stay
+/// stateless and therefore be shared through a static like the deleters are.
+pub trait Backoff: Sync {
+    fn snooze(&self, step: u32);
+}
+
+/// Retries right away, which is what the list has always done and is still the default.
+pub struct NoBackoff;
+
+impl Default for NoBackoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NoBackoff {
+    pub const fn new() -> Self {
+        NoBackoff
+    }
+}
+
+impl Backoff for NoBackoff {
+    fn snooze(&self, _step: u32) {}
+}
+
+/// Spins for a short and fixed while before every retry, which keeps a retrying thread from
+/// pulling the contended cache line away from the thread that is about to finish.
+pub struct Spin;
+
+impl Default for Spin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Spin {
+    pub const fn new() -> Self {
+        Spin
+    }
+}
+
+impl Backoff for Spin {
+    fn snooze(&self, _step: u32) {
+        for _ in 0..SPINS {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Doubles the number of spins with every retry and gives up the time slice once that gets
+/// long, so that a thread waiting on a preempted one stops burning the core it needs. Without
+/// the std feature there is nobody to yield to and it keeps spinning instead.
+pub struct Exponential;
+
+impl Default for Exponential {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Exponential {
+    pub const fn new() -> Self {
+        Exponential
+    }
+}
+
+impl Backoff for Exponential {
+    fn snooze(&self, step: u32) {
+        if step <= SPIN_LIMIT {
+            for _ in 0..1 << step {
+                core::hint::spin_loop();
+            }
+        } else {
+            sync::yield_now();
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use crate::hazard::{DropBox, HazPtrHolder};
+use crate::list::Node;
+use crate::sync::CachePadded;
+use crate::sync::atomic::{AtomicPtr, AtomicUsize, fence};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
+
+static DELETER: DropBox = DropBox::new();
+
+// Number of subscribers a broadcast can have at once.
+const SUBSCRIBERS: usize = 64;
+
+// What the cursor of a slot holds while nobody subscribed through it. Anything else is the index
+// of the next node the subscriber is going to read.
+const FREE: usize = usize::MAX;
+
+/// Returned by `Subscriber::try_recv`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// Nothing was published that this subscriber has not seen yet.
+    Empty,
+    /// The subscriber fell behind by more than the lag limit and skipped this many values. It goes
+    /// on with the oldest value still around.
+    Lagged(usize),
+}
+
+/// A broadcast where every subscriber sees every value published after it subscribed, in the
+/// order they were published.
+///
+/// The values sit in the same nodes the list uses, linked from the oldest towards the newest
+/// through their prev pointers. Publishers link new nodes in front of the head, every subscriber
+/// walks towards the head with a cursor of its own that keeps the node it stands on protected, and
+/// a node is only retired once every subscriber has moved past it. A subscriber that falls behind
+/// by more than the lag limit stops holding the others up, it loses the values that got retired in
+/// the meantime and is told how many those were.
+pub struct Broadcast<T: 'static> {
+    // the node published last
+    head: CachePadded<AtomicPtr<Node<Option<T>>>>,
+    // the oldest node that was not retired yet
+    tail: CachePadded<AtomicPtr<Node<Option<T>>>>,
+    // the index of the tail, raised before the node it moved past gets retired so that a lagging
+    // subscriber can tell its next node may be gone
+    tail_index: CachePadded<AtomicUsize>,
+    cursors: Box<[CachePadded<AtomicUsize>]>,
+    lag_limit: usize,
+}
+
+// Subscribers clone the values out of nodes they share with each other, and the last node
+// standing gets dropped wherever the broadcast does.
+unsafe impl<T: 'static> Send for Broadcast<T> where T: Send + Sync {}
+unsafe impl<T: 'static> Sync for Broadcast<T> where T: Send + Sync {}
+
+impl<T: 'static> Default for Broadcast<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: 'static> Broadcast<T> {
+    /// Creates a broadcast without a lag limit, a subscriber that stops receiving keeps every value
+    /// published after it alive.
+    pub fn new() -> Self {
+        Self::with_lag_limit(usize::MAX)
+    }
+
+    /// Creates a broadcast that keeps at most `lag_limit` values around for its slowest
+    /// subscribers. Whoever falls further behind than that skips ahead.
+    pub fn with_lag_limit(lag_limit: usize) -> Self {
+        // The head always points at a node, a subscriber that arrives stands on it and waits for
+        // the next one. The first one carries no value.
+        let sentinel = Box::into_raw(Box::new(Node::new(None)));
+        let cursors: Vec<_> = (0..SUBSCRIBERS)
+            .map(|_| CachePadded::new(AtomicUsize::new(FREE)))
+            .collect();
+        Self {
+            head: CachePadded::new(AtomicPtr::new(sentinel)),
+            tail: CachePadded::new(AtomicPtr::new(sentinel)),
+            tail_index: CachePadded::new(AtomicUsize::new(0)),
+            cursors: cursors.into_boxed_slice(),
+            lag_limit,
+        }
+    }
+
+    /// Publishes the value to every current subscriber.
+    pub fn publish(&self, value: T) {
+        let node = Box::into_raw(Box::new(Node::new(Some(value))));
+        let mut holder = HazPtrHolder::default();
+        loop {
+            let head = unsafe { holder.load(&self.head) }.expect("The head is never null");
+            unsafe {
+                (*node)
+                    .index
+                    .store(head.index.load(Ordering::Relaxed) + 1, Ordering::Relaxed)
+            };
+            if self
+                .head
+                .compare_exchange(head.data, node, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                // Nobody walks past the old head before this, so it can not have been retired.
+                head.prev.store(node, Ordering::Release);
+                break;
+            }
+        }
+        core::mem::drop(holder);
+        self.trim();
+    }
+
+    /// Subscribes to every value published from now on. Returns None if the broadcast has as many
+    /// subscribers as it can take.
+    pub fn subscribe(&self) -> Option<Subscriber<'_, T>> {
+        let slot = self.cursors.iter().position(|cursor| {
+            // a cursor at zero holds every node back until we know where we start
+            cursor
+                .compare_exchange(FREE, 0, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        })?;
+        // Pairs with the fence in trim: either the trimming thread sees the cursor claimed or we
+        // see a head at least as new as the one it trims up to.
+        fence(Ordering::SeqCst);
+        let mut current = HazPtrHolder::default();
+        let head = unsafe { current.load(&self.head) }
+            .expect("The head is never null")
+            .keep();
+        let next = unsafe { (*head).index.load(Ordering::Relaxed) } + 1;
+        self.cursors[slot].store(next, Ordering::Release);
+        Some(Subscriber {
+            broadcast: self,
+            slot,
+            current,
+            spare: HazPtrHolder::default(),
+            node: head,
+            consumed: true,
+            next,
+        })
+    }
+
+    // Retires the nodes at the tail that every subscriber has moved past, or that are so far
+    // behind the head that the lag limit gives up on them.
+    fn trim(&self) {
+        fence(Ordering::SeqCst);
+        let mut holder = HazPtrHolder::default();
+        let head = unsafe { holder.load(&self.head) }
+            .expect("The head is never null")
+            .index
+            .load(Ordering::Relaxed);
+        core::mem::drop(holder);
+        let floor = head.saturating_sub(self.lag_limit);
+        let keep = self
+            .cursors
+            .iter()
+            .map(|cursor| cursor.load(Ordering::Acquire))
+            .filter(|&next| next != FREE)
+            .map(|next| next.max(floor))
+            .fold(head, usize::min);
+        let mut tail_holder = HazPtrHolder::default();
+        loop {
+            let tail = unsafe { tail_holder.load(&self.tail) }.expect("The tail is never null");
+            if tail.index.load(Ordering::Relaxed) >= keep {
+                break;
+            }
+            // a node whose successor is not linked yet is still the head as far as the
+            // subscribers can tell
+            let prev = tail.prev.load(Ordering::Acquire);
+            if prev.is_null() {
+                break;
+            }
+            let index = tail.index.load(Ordering::Relaxed);
+            let tail = tail.data;
+            if self
+                .tail
+                .compare_exchange(tail, prev, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                // every node is linked in front of the one published right before it, so the
+                // indices along the prev pointers go up one at a time
+                self.tail_index.fetch_max(index + 1, Ordering::SeqCst);
+                let mut retire_holder = HazPtrHolder::default();
+                let wrapper = unsafe {
+                    retire_holder.swap(&AtomicPtr::new(tail), core::ptr::null_mut(), &DELETER)
+                };
+                if let Some(mut wrapper) = wrapper {
+                    wrapper.defer_retire();
+                }
+            }
+        }
+        HazPtrHolder::try_reclaim();
+    }
+}
+
+impl<T: 'static> Drop for Broadcast<T> {
+    fn drop(&mut self) {
+        // Every subscriber borrowed the broadcast, so whatever is still linked belongs to us alone.
+        let mut current = self.tail.load(Ordering::Acquire);
+        while !current.is_null() {
+            let node = unsafe { Box::from_raw(current) };
+            current = node.prev.load(Ordering::Acquire);
+        }
+    }
+}
+
+/// A subscription to a `Broadcast`, receives every value published after it was taken out.
+pub struct Subscriber<'a, T: 'static> {
+    broadcast: &'a Broadcast<T>,
+    slot: usize,
+    // protects the node the subscriber stands on, the spare one takes the next node before the two
+    // trade places
+    current: HazPtrHolder,
+    spare: HazPtrHolder,
+    node: *mut Node<Option<T>>,
+    // whether the value of the node was received already
+    consumed: bool,
+    // the index of the next node to receive, mirrored in the cursor of the slot
+    next: usize,
+}
+
+unsafe impl<T: 'static> Send for Subscriber<'_, T> where T: Send + Sync {}
+
+impl<T: 'static> Subscriber<'_, T>
+where
+    T: Clone,
+{
+    /// Receives the next value, if one was published that this subscriber has not seen yet.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        loop {
+            if !self.consumed {
+                self.consumed = true;
+                self.advance(unsafe { (*self.node).index.load(Ordering::Relaxed) } + 1);
+                if let Some(value) = unsafe { &*(*self.node).value } {
+                    return Ok(value.clone());
+                }
+                continue;
+            }
+            let next = unsafe { (*self.node).prev.load(Ordering::Acquire) };
+            if next.is_null() {
+                return Err(TryRecvError::Empty);
+            }
+            unsafe { self.spare.load(&AtomicPtr::new(next)) }
+                .expect("Has to be there")
+                .keep();
+            core::mem::swap(&mut self.current, &mut self.spare);
+            // Once the lag limit gave up on us the next node may have been retired before we
+            // protected it. The index of the tail is raised before that happens and the fence in
+            // protect orders this load after the hazard is published.
+            let tail = self.broadcast.tail_index.load(Ordering::SeqCst);
+            if tail > self.next {
+                let tail = unsafe { self.current.load(&self.broadcast.tail) }
+                    .expect("The tail is never null")
+                    .keep();
+                let index = unsafe { (*tail).index.load(Ordering::Relaxed) };
+                let skipped = index - self.next;
+                self.node = tail;
+                self.consumed = false;
+                self.advance(index);
+                return Err(TryRecvError::Lagged(skipped));
+            }
+            self.node = next;
+            self.consumed = false;
+        }
+    }
+}
+
+impl<T: 'static> Subscriber<'_, T> {
+    fn advance(&mut self, next: usize) {
+        self.next = next;
+        self.broadcast.cursors[self.slot].store(next, Ordering::Release);
+    }
+}
+
+impl<T: 'static> Drop for Subscriber<'_, T> {
+    fn drop(&mut self) {
+        self.broadcast.cursors[self.slot].store(FREE, Ordering::Release);
+    }
+}
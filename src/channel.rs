@@ -0,0 +1,142 @@
+use crate::list::{Closed, LinkedList};
+use crate::sync::atomic::AtomicUsize;
+use alloc::sync::Arc;
+use core::sync::atomic::Ordering;
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+// The list both ends share. Closing it is how either side tells the other it went away: once the
+// last sender is gone the receivers drain what is left and then see the list closed, once the last
+// receiver is gone every send hands its value back.
+struct Shared<T: 'static> {
+    list: LinkedList<T>,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+}
+
+/// Returned by `send` when every receiver is gone, with the value that could not be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+/// Returned by `recv` once every sender is gone and every value sent was received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// Nothing to receive right now, but a sender may still send.
+    Empty,
+    /// Every sender is gone and every value sent was received.
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    /// Nothing arrived for the whole of the timeout.
+    Timeout,
+    /// Every sender is gone and every value sent was received.
+    Disconnected,
+}
+
+/// Creates a channel that any number of senders and receivers can share, with a `LinkedList`
+/// carrying the values from one end to the other. Values are received in the order they were
+/// sent, each by exactly one receiver.
+pub fn channel<T: 'static>() -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        list: LinkedList::new(),
+        senders: AtomicUsize::new(1),
+        receivers: AtomicUsize::new(1),
+    });
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+/// The sending end of a channel, can be cloned to send from more threads.
+pub struct Sender<T: 'static> {
+    shared: Arc<Shared<T>>,
+}
+
+// Values only ever move through the list, no two threads get at the same value, so the handles
+// can be shared as long as the values can be sent.
+unsafe impl<T: 'static> Send for Sender<T> where T: Send {}
+unsafe impl<T: 'static> Sync for Sender<T> where T: Send {}
+
+impl<T: 'static> Sender<T> {
+    /// Sends the value, or hands it back if every receiver is gone.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.shared.list.insert_from_head(value).map_err(SendError)
+    }
+}
+
+impl<T: 'static> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T: 'static> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.list.close();
+        }
+    }
+}
+
+/// The receiving end of a channel, can be cloned to receive on more threads.
+pub struct Receiver<T: 'static> {
+    shared: Arc<Shared<T>>,
+}
+
+unsafe impl<T: 'static> Send for Receiver<T> where T: Send {}
+unsafe impl<T: 'static> Sync for Receiver<T> where T: Send {}
+
+impl<T: 'static> Receiver<T> {
+    /// Receives a value if there is one, without waiting.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.shared.list.delete_from_tail() {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(TryRecvError::Empty),
+            Err(Closed) => Err(TryRecvError::Disconnected),
+        }
+    }
+
+    /// Receives a value, parking the thread until one is sent or every sender is gone.
+    #[cfg(feature = "std")]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.shared.list.pop_blocking().map_err(|Closed| RecvError)
+    }
+
+    /// Same as `recv`, but gives up once nothing arrived for the whole of `timeout`.
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        match self.shared.list.pop_timeout(timeout) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(RecvTimeoutError::Timeout),
+            Err(Closed) => Err(RecvTimeoutError::Disconnected),
+        }
+    }
+}
+
+impl<T: 'static> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.receivers.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T: 'static> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        if self.shared.receivers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.list.close();
+        }
+    }
+}
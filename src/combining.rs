@@ -0,0 +1,304 @@
+use crate::Node;
+use crate::backoff::{Backoff, NoBackoff};
+use crate::list::Closed;
+use crate::pool;
+use crate::sync::CachePadded;
+use crate::sync::atomic::{AtomicBool, AtomicUsize};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::sync::atomic::Ordering;
+
+static NO_BACKOFF: NoBackoff = NoBackoff::new();
+
+// Number of requests that can be published at the same time. A thread that finds all of them taken
+// waits for one to come free, so this only needs to be about as large as the number of threads
+// expected to hit the list at once.
+const SLOTS: usize = 64;
+
+// The states a slot goes through. The owner moves it from FREE to CLAIMED, publishes its request
+// by storing INSERT, DELETE or CLOSE, and the combiner answers with DONE, or with CLOSED for an
+// insert it turned away and a delete that found the list closed and empty, after which the owner
+// takes the result and hands the slot back by storing FREE.
+const FREE: usize = 0;
+const CLAIMED: usize = 1;
+const INSERT: usize = 2;
+const DELETE: usize = 3;
+const CLOSE: usize = 4;
+const DONE: usize = 5;
+const CLOSED: usize = 6;
+
+struct Slot<T> {
+    state: AtomicUsize,
+    // written by the owner before it publishes an insert, the node is handed back through it if
+    // the list was closed
+    node: UnsafeCell<*mut Node<T>>,
+    // written by the combiner before it answers a delete
+    result: UnsafeCell<Option<T>>,
+}
+
+/// A flat combining front-end over the same nodes `LinkedList` uses. Instead of every thread
+/// working on the list through descriptors and helping the others, threads publish their
+/// operation in a slot and whichever of them manages to take the combiner lock applies every
+/// published operation in one go. Under heavy contention that trades the redundant helping for a
+/// single thread walking the slots while the cache lines of the list stay with it.
+///
+/// It offers the basic operations of `LinkedList` with the same signatures, inserting, deleting,
+/// closing and the length. Capacity limits, waiting for values, the wait-free mode and the batch,
+/// conditional and middle-of-the-list operations are only offered by `LinkedList`.
+pub struct CombiningList<T> {
+    length: CachePadded<AtomicUsize>,
+    // only ever set by the combiner, so a close is ordered against the inserts and deletes like
+    // any other request
+    closed: AtomicBool,
+    // held by the combiner, only the thread holding it touches head and tail
+    lock: CachePadded<AtomicBool>,
+    head: UnsafeCell<*mut Node<T>>,
+    tail: UnsafeCell<*mut Node<T>>,
+    slots: Box<[CachePadded<Slot<T>>]>,
+    pooled: bool,
+    backoff: &'static dyn Backoff,
+    marker: PhantomData<Node<T>>,
+}
+
+unsafe impl<T> Send for CombiningList<T> where T: Send {}
+unsafe impl<T> Sync for CombiningList<T> where T: Send {}
+
+impl<T> Default for CombiningList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> CombiningList<T> {
+    pub fn new() -> Self {
+        Self::build(false)
+    }
+
+    /// Same as `LinkedList::with_pool`, nodes are taken from and handed back to the process wide
+    /// recycling pool.
+    pub fn with_pool() -> Self {
+        Self::build(true)
+    }
+
+    fn build(pooled: bool) -> Self {
+        let slots: Vec<_> = (0..SLOTS)
+            .map(|_| {
+                CachePadded::new(Slot {
+                    state: AtomicUsize::new(FREE),
+                    node: UnsafeCell::new(core::ptr::null_mut()),
+                    result: UnsafeCell::new(None),
+                })
+            })
+            .collect();
+        Self {
+            length: CachePadded::new(AtomicUsize::new(0)),
+            closed: AtomicBool::new(false),
+            lock: CachePadded::new(AtomicBool::new(false)),
+            head: UnsafeCell::new(core::ptr::null_mut()),
+            tail: UnsafeCell::new(core::ptr::null_mut()),
+            slots: slots.into_boxed_slice(),
+            pooled,
+            backoff: &NO_BACKOFF,
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets what a thread does while it waits for its request to be answered or for a slot to
+    /// come free, see `LinkedList::with_backoff`.
+    pub fn with_backoff(mut self, backoff: &'static dyn Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Inserts the value unless the list was closed, in which case it is handed back.
+    pub fn insert_from_head(&self, value: T) -> Result<(), T> {
+        let node = if self.pooled {
+            pool::alloc(Node::new(value))
+        } else {
+            Box::into_raw(Box::new(Node::new(value)))
+        };
+        let slot = self.claim();
+        unsafe { *slot.node.get() = node };
+        slot.state.store(INSERT, Ordering::SeqCst);
+        let answer = self.wait(slot);
+        slot.state.store(FREE, Ordering::SeqCst);
+        if answer == CLOSED {
+            let value = unsafe { ManuallyDrop::take(&mut (*node).value) };
+            unsafe { (*node).value_moved.store(true, Ordering::SeqCst) };
+            self.free(node);
+            return Err(value);
+        }
+        Ok(())
+    }
+
+    /// Takes the oldest value out of the list. None means the list is empty for now, `Closed`
+    /// that it was closed and will stay empty.
+    pub fn delete_from_tail(&self) -> Result<Option<T>, Closed> {
+        let slot = self.claim();
+        slot.state.store(DELETE, Ordering::SeqCst);
+        let answer = self.wait(slot);
+        let ret = unsafe { (*slot.result.get()).take() };
+        slot.state.store(FREE, Ordering::SeqCst);
+        if answer == CLOSED {
+            return Err(Closed);
+        }
+        Ok(ret)
+    }
+
+    /// Closes the list for good, see `LinkedList::close`. The combiner applies it in line with
+    /// the other requests, so an insert either gets its value in before it or gets it back.
+    pub fn close(&self) {
+        let slot = self.claim();
+        slot.state.store(CLOSE, Ordering::SeqCst);
+        self.wait(slot);
+        slot.state.store(FREE, Ordering::SeqCst);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// The number of values in the list. Only the combiner changes the list and it bumps the
+    /// count in the same step as it links or unlinks a node, so unlike `LinkedList::approx_len`
+    /// this never shows a length the list did not have.
+    pub fn len(&self) -> usize {
+        self.length.load(Ordering::SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Kept for the same API as `LinkedList`, the count is exact here already.
+    pub fn approx_len(&self) -> usize {
+        self.length.load(Ordering::Relaxed)
+    }
+
+    fn claim(&self) -> &Slot<T> {
+        let mut step = 0;
+        loop {
+            for slot in self.slots.iter() {
+                if slot
+                    .state
+                    .compare_exchange(FREE, CLAIMED, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+                {
+                    return slot;
+                }
+            }
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    // Waits until the request in the slot has been answered, becoming the combiner whenever the
+    // lock is free, and returns the answer. A combiner always finds its own request because it
+    // was published before the lock was taken.
+    fn wait(&self, slot: &Slot<T>) -> usize {
+        let mut step = 0;
+        loop {
+            let state = slot.state.load(Ordering::SeqCst);
+            if state == DONE || state == CLOSED {
+                return state;
+            }
+            if self
+                .lock
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                self.combine();
+                self.lock.store(false, Ordering::SeqCst);
+            } else {
+                self.backoff.snooze(step);
+                step = step.saturating_add(1);
+            }
+        }
+    }
+
+    // Applies every request that is published right now. Only ever called with the lock held.
+    fn combine(&self) {
+        for slot in self.slots.iter() {
+            match slot.state.load(Ordering::SeqCst) {
+                INSERT if self.closed.load(Ordering::Relaxed) => {
+                    slot.state.store(CLOSED, Ordering::SeqCst);
+                }
+                INSERT => {
+                    unsafe { self.link(*slot.node.get()) };
+                    slot.state.store(DONE, Ordering::SeqCst);
+                }
+                DELETE => {
+                    let value = unsafe { self.unlink() };
+                    let answer = if value.is_none() && self.closed.load(Ordering::Relaxed) {
+                        CLOSED
+                    } else {
+                        DONE
+                    };
+                    unsafe { *slot.result.get() = value };
+                    slot.state.store(answer, Ordering::SeqCst);
+                }
+                CLOSE => {
+                    self.closed.store(true, Ordering::SeqCst);
+                    slot.state.store(DONE, Ordering::SeqCst);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    unsafe fn link(&self, node: *mut Node<T>) {
+        unsafe {
+            let head = *self.head.get();
+            if head.is_null() {
+                *self.tail.get() = node;
+            } else {
+                (*head).prev.store(node, Ordering::SeqCst);
+            }
+            *self.head.get() = node;
+        }
+        self.length.fetch_add(1, Ordering::SeqCst);
+    }
+
+    unsafe fn unlink(&self) -> Option<T> {
+        let tail = unsafe { *self.tail.get() };
+        if tail.is_null() {
+            return None;
+        }
+        unsafe {
+            let prev = (*tail).prev.load(Ordering::SeqCst);
+            *self.tail.get() = prev;
+            if prev.is_null() {
+                *self.head.get() = core::ptr::null_mut();
+            }
+        }
+        self.length.fetch_sub(1, Ordering::SeqCst);
+        // the node only drops its value itself if it was never moved out
+        let value = unsafe { ManuallyDrop::take(&mut (*tail).value) };
+        unsafe { (*tail).value_moved.store(true, Ordering::SeqCst) };
+        self.free(tail);
+        Some(value)
+    }
+
+    // Nobody but the combiner ever sees a node, so there is nothing to retire through the hazard
+    // domain and the node can go straight back to wherever it came from.
+    fn free(&self, node: *mut Node<T>) {
+        if self.pooled {
+            unsafe { pool::recycle(node) };
+        } else {
+            let _ = unsafe { Box::from_raw(node) };
+        }
+    }
+}
+
+impl<T> Drop for CombiningList<T> {
+    fn drop(&mut self) {
+        let mut current = *self.tail.get_mut();
+        while !current.is_null() {
+            let prev = unsafe { (*current).prev.load(Ordering::SeqCst) };
+            self.free(current);
+            current = prev;
+        }
+    }
+}
@@ -1,22 +1,61 @@
 #![allow(dead_code)]
 #![allow(unused_must_use)]
 #![allow(unused)]
-use crate::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+use crate::announce::Announcement;
+use crate::list::DeleteIfError;
+use crate::list::{self, Claim, REMOVED, TAKEN, Unclaim};
+use crate::pool::{self, Recycle};
+use crate::sync::{
+    self,
+    atomic::{AtomicBool, AtomicPtr, AtomicUsize, fence},
+};
 use crate::{Deleter, DropBox, DropPointer, HazPtrHolder, HazPtrObject};
 use crate::{LinkedList, Node};
-use std::mem::MaybeUninit;
-use std::ops::DerefMut;
-use std::sync::atomic::Ordering;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::DerefMut;
+use core::sync::atomic::Ordering;
 
 static DELETER1: DropBox = DropBox::new();
 static DELETER2: DropPointer = DropPointer::new();
+static RECYCLE: Recycle = Recycle::new();
 
 #[derive(Copy, Clone)]
 pub(crate) enum Operation {
     Insert,
     Delete,
+    Close,
+    // takes the whole list out at once from the head descriptor
+    Drain,
+    // keeps the tail descriptor for the drain it points at
+    Hold,
+    // takes a node that lost its value to a remove out of the middle of the list from the tail
+    // descriptor, where no delete can move the tail past the nodes around it in the meantime
+    Unlink,
 }
 
+// What became of an insert that went through a descriptor.
+pub(crate) enum Inserted {
+    Linked,
+    // the head turned out to be null and the caller has to go through the null path
+    Empty,
+    // the list was at its capacity limit or closed, the node was not linked
+    Rejected,
+}
+
+// The status an insert descriptor moves to instead of 1 when linking its node would take the list
+// past its capacity or the list got closed. Deciding it through the status means all helpers agree
+// on it even though the tail keeps moving underneath them. A drain that found the ends of the list
+// moved on gives up the same way.
+const REJECTED: usize = 3;
+
+// Where a conditional delete stands with its predicate. Every other descriptor starts out approved.
+const ASKING: usize = 0;
+const APPROVED: usize = 1;
+const DECLINED: usize = 2;
+
 // Status field helped other helper threads to help efficiently by looking at how much
 // of the task has been completed and the pending field was introduced to keep a broad eye
 // on whether the entire task has been completed. It was there for other threads to try
@@ -30,254 +69,449 @@ pub(crate) struct Descriptor<T> {
     current: *mut Node<T>, // newest change to solve the biggest problems
     success: AtomicBool,
     next: *mut Node<T>,
+    // the node that becomes the tail once a delete is done. It is read by the initiator before the
+    // descriptor is published and never changes afterwards, a null prev means current is the last
+    // node and the delete has to empty the list, which is the only case where it touches the head
+    prev: *mut Node<T>,
+    // the newest node of the chain an operation works on and how many nodes that chain holds. It
+    // is just next for an insert and current for a delete unless a whole batch goes in or out at
+    // once, in which case next or current is the oldest node of the batch and the rest hangs off
+    // its prev pointers. An unlink takes out current, which is in front of next and behind prev,
+    // and keeps the tail it walked from in last
+    last: *mut Node<T>,
+    count: usize,
+    // how many nodes of a batch or a drain had lost their value to a remove, counted by the
+    // helper that claims the values before the status moves on
+    removed: AtomicUsize,
+    // the unlinks counter of the list before a batch walked its chain
+    unlinks: usize,
     status: AtomicUsize,
     pending: AtomicBool,
     op: Operation,
     deleter: &'static dyn Deleter,
     retired: AtomicBool,
-    // we use maybeuninit to make sure that we can have unitialized instances when we create the
-    // descriptor in both insertion and deletion.. also to ensure safe getting back of the T on
-    // delete we have to introduce another flag to check whether or not the taken value pointer has
-    // been swapped by any helper or the main thread to actually store in it a maybeuninit which
-    // contains the T... therefore we introduce the init_stored field which if loads to false then
-    // we do not assume_init() on the maybeuninit
-    taken_value: AtomicPtr<MaybeUninit<T>>,
+    // set once the value of an announced delete was moved into its announcement. The value of
+    // any other delete stays in its node until the initiator takes it out
     init_stored: AtomicBool,
-}
-
-enum SwapResult {
-    Success,
-    //Failure will include both failure in swapping and failure in the operation that occured later
-    //as in both the cases we have to loop back
-    Failure,
+    // the announced operation this descriptor works for in wait-free mode and its phase, null
+    // otherwise
+    announcement: *const Announcement<T>,
+    phase: usize,
+    // the drain a Hold descriptor keeps the tail descriptor for, null for everything else
+    partner: *mut Descriptor<T>,
+    // whether the initiator of a conditional delete let it go ahead, nobody moves past status 0
+    // before it decided
+    approval: AtomicUsize,
 }
 
 unsafe impl<T> Send for Descriptor<T> where T: Send {}
 unsafe impl<T> Sync for Descriptor<T> where T: Send {}
 
-impl<T> Drop for LinkedList<T> {
+impl<T: 'static> Drop for LinkedList<T> {
     fn drop(&mut self) {
-        let mut holder = HazPtrHolder::default();
-        let mut guard = unsafe { holder.load(&self.descriptor) };
-        if let Some(ref mut thing) = guard {
-            let deleter = unsafe { (*thing.data).deleter };
-            let mut swap_holder = HazPtrHolder::default();
+        for slot in [&self.head_descriptor, &self.tail_descriptor] {
+            let mut holder = HazPtrHolder::default();
+            let mut guard = unsafe { holder.load(slot) };
+            if let Some(ref mut thing) = guard {
+                let deleter = unsafe { (*thing.data).deleter };
+                let mut swap_holder = HazPtrHolder::default();
+                let wrapper = unsafe {
+                    swap_holder.swap(&AtomicPtr::new(thing.data), core::ptr::null_mut(), deleter)
+                };
+                if let Some(mut wrapper) = wrapper
+                    && unsafe {
+                        (*wrapper.inner).retired.compare_exchange(
+                            false,
+                            true,
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                        )
+                    }
+                    .is_ok()
+                {
+                    wrapper.defer_retire();
+                }
+            }
+        }
+        // Every node that is still linked owns a value that nobody has taken out. We walk from the
+        // tail towards the head through the prev pointers and retire all of them before reclaiming
+        // even once, so that a panicking destructor can only ever interrupt the reclamation and
+        // never leave part of the list unretired.
+        let mut current = self.tail.swap(core::ptr::null_mut(), Ordering::AcqRel);
+        self.head.store(core::ptr::null_mut(), Ordering::Release);
+        while !current.is_null() {
+            let prev = unsafe { (*current).prev.load(Ordering::Acquire) };
+            let mut node_holder = HazPtrHolder::default();
             let wrapper = unsafe {
-                swap_holder.swap(&AtomicPtr::new(thing.data), std::ptr::null_mut(), deleter)
+                node_holder.swap(
+                    &AtomicPtr::new(current),
+                    core::ptr::null_mut(),
+                    self.deleter(),
+                )
             };
             if let Some(mut wrapper) = wrapper {
-                if unsafe {
-                    (&(*wrapper.inner).retired).compare_exchange(
-                        false,
-                        true,
-                        Ordering::SeqCst,
-                        Ordering::SeqCst,
-                    )
-                }
-                .is_ok()
-                {
-                    wrapper.retire();
-                }
+                wrapper.defer_retire();
             }
-        } else {
-            return;
+            current = prev;
         }
-        std::mem::drop(guard);
         HazPtrHolder::try_reclaim();
     }
 }
 
+// Declines a conditional delete that is still waiting for its predicate when dropped.
+struct Decline<'a>(&'a AtomicUsize);
+
+impl Drop for Decline<'_> {
+    fn drop(&mut self) {
+        self.0
+            .compare_exchange(ASKING, DECLINED, Ordering::AcqRel, Ordering::Acquire);
+    }
+}
+
 impl<T> Descriptor<T> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         current: *mut Node<T>,
-        success: AtomicBool,
         next: *mut Node<T>,
-        status: AtomicUsize,
-        pending: AtomicBool,
+        prev: *mut Node<T>,
         op: Operation,
         deleter: &'static dyn Deleter,
-        retired: AtomicBool,
-        taken_value: AtomicPtr<MaybeUninit<T>>,
-        init_stored: AtomicBool,
+        announced: Option<(&Announcement<T>, usize)>,
     ) -> Self {
+        let (announcement, phase) = match announced {
+            Some((announcement, phase)) => (announcement as *const Announcement<T>, phase),
+            None => (core::ptr::null(), 0),
+        };
         Self {
             current,
-            success,
+            success: AtomicBool::new(false),
             next,
-            status,
-            pending,
+            prev,
+            last: next,
+            count: 1,
+            removed: AtomicUsize::new(0),
+            unlinks: 0,
+            status: AtomicUsize::new(0),
+            pending: AtomicBool::new(true),
             op,
             deleter,
-            retired,
-            taken_value,
-            init_stored,
+            retired: AtomicBool::new(false),
+            init_stored: AtomicBool::new(false),
+            announcement,
+            phase,
+            partner: core::ptr::null_mut(),
+            approval: AtomicUsize::new(APPROVED),
+        }
+    }
+
+    // Makes the descriptor link or detach the whole chain of count nodes up to last.
+    fn chain(mut self, last: *mut Node<T>, count: usize) -> Self {
+        self.last = last;
+        self.count = count;
+        self
+    }
+
+    // Makes a batch give up if anything was unlinked from the middle of the list since the
+    // unlinks counter read the given value.
+    fn seen(mut self, unlinks: usize) -> Self {
+        self.unlinks = unlinks;
+        self
+    }
+
+    // Makes a delete wait for its initiator to ask the predicate.
+    fn conditional(self) -> Self {
+        self.approval.store(ASKING, Ordering::Relaxed);
+        self
+    }
+
+    // Makes a Hold descriptor keep the tail descriptor for the given drain.
+    fn hold(mut self, partner: *mut Descriptor<T>) -> Self {
+        self.partner = partner;
+        self
+    }
+
+    // Whether somebody already finished the announced operation this descriptor works for, in
+    // which case it must not take effect a second time. Always false outside wait-free mode.
+    fn announced_finished(&self) -> bool {
+        !self.announcement.is_null() && unsafe { (*self.announcement).finished(self.phase) }
+    }
+
+    fn finish_announced(&self) {
+        if !self.announcement.is_null() {
+            unsafe { (*self.announcement).finish(self.phase) };
         }
     }
 }
 
-impl<T> LinkedList<T> {
-    pub(crate) fn insert(&self, next: *mut Node<T>) {
+impl<T: 'static> LinkedList<T> {
+    // Allocation, disposal and retirement all go through these three so that a pooled list never
+    // hands a block to the allocator and a plain one never touches the pool.
+    pub(crate) fn alloc<U>(&self, value: U) -> *mut U {
+        if self.pooled {
+            pool::alloc(value)
+        } else {
+            Box::into_raw(Box::new(value))
+        }
+    }
+
+    // Only for blocks that were never published to other threads, everything else has to be
+    // retired through the hazard domain with the deleter below.
+    pub(crate) fn discard<U>(&self, ptr: *mut U) {
+        if self.pooled {
+            unsafe { pool::recycle(ptr) };
+        } else {
+            let _ = unsafe { Box::from_raw(ptr) };
+        }
+    }
+
+    pub(crate) fn deleter(&self) -> &'static dyn Deleter {
+        if self.pooled { &RECYCLE } else { &DELETER1 }
+    }
+
+    // Returns Empty if the head turned out to be null, in which case the caller has to go through
+    // the null path again. A descriptor can only ever link a node in front of an existing head, it
+    // never touches the tail, so installing one for an empty list would leave the tail null while
+    // the head points at the new node.
+    // In wait-free mode it works for an announced operation and also returns Linked once that one
+    // got finished by somebody else, whether the node got linked or turned away.
+    pub(crate) fn insert(
+        &self,
+        next: *mut Node<T>,
+        announced: Option<(&Announcement<T>, usize)>,
+    ) -> Inserted {
+        self.insert_chain(next, next, 1, announced)
+    }
+
+    // Links the count nodes from first to last, which the caller chained together through their
+    // prev pointers, in front of the head with a single descriptor.
+    pub(crate) fn insert_chain(
+        &self,
+        first: *mut Node<T>,
+        last: *mut Node<T>,
+        count: usize,
+        announced: Option<(&Announcement<T>, usize)>,
+    ) -> Inserted {
+        let next = first;
+        let mut step = 0;
         loop {
+            if let Some((announcement, phase)) = announced
+                && announcement.finished(phase)
+            {
+                return Inserted::Linked;
+            }
             let mut current_node_holder = HazPtrHolder::default();
             let mut current_node_guard = unsafe { current_node_holder.load(&self.head) };
             let current_node = if let Some(ref mut guard) = current_node_guard {
                 guard.data
             } else {
-                std::ptr::null_mut()
+                return Inserted::Empty;
             };
-            let uninit = Box::into_raw(Box::new(MaybeUninit::uninit()));
-            let new_descriptor: *mut Descriptor<T> = Box::into_raw(Box::new(Descriptor::new(
-                current_node,
-                AtomicBool::new(false),
-                next,
-                AtomicUsize::new(0),
-                AtomicBool::new(true),
-                Operation::Insert,
-                &DELETER1,
-                AtomicBool::new(false),
-                AtomicPtr::new(uninit),
-                AtomicBool::new(false),
-            )));
-            if self.descriptor.load(Ordering::Acquire).is_null() {
-                if let SwapResult::Success = self.swap_null_insert(new_descriptor) {
-                    return;
-                }
-            } else {
-                // The idea is to first load the current descriptor into a hazard pointer if we do
-                // not get back a guard we just simply loop back. If we get back a guard we try to
-                // get to the pending field of the descriptor and check whether it is true or
-                // false, if not false then we just help. If it is false we try to CAS the descriptor
-                // with our new descriptor, if successful we proceed with our operation, otherwise
-                // we just help.
-                let mut pending_holder = HazPtrHolder::default();
-                let mut pending_holder_guard = unsafe { pending_holder.load(&self.descriptor) };
-                if let Some(ref mut thing) = pending_holder_guard {
-                    let mut new_descriptor_holder = HazPtrHolder::default();
-                    let mut new_descriptor_guard = unsafe {
-                        new_descriptor_holder
-                            .load(&AtomicPtr::new(new_descriptor))
-                            .expect("Has to be there")
-                    };
-                    if !unsafe { (*thing.data).pending.load(Ordering::SeqCst) } {
-                        if self
-                            .descriptor
-                            .compare_exchange(
-                                thing.data,
-                                new_descriptor,
-                                Ordering::SeqCst,
-                                Ordering::SeqCst,
-                            )
-                            .is_ok()
-                        {
-                            let mut swapholder = HazPtrHolder::default();
-                            let mut wrapper = unsafe {
-                                swapholder.swap(
-                                    &AtomicPtr::new(thing.data),
-                                    std::ptr::null_mut(),
-                                    ((*thing.data).deleter),
-                                )
-                            };
-                            if let Some(mut wrapper) = wrapper {
-                                // this code path ensures that the descriptor is retired only once
-                                // but double retirement can arise due to the drop implementation
-                                // therefore we introduce the retired field in descriptor...
-                                // calling the CAS in this code path seems redundant as the code
-                                // path already guarantees safety but this has to be done to ensure
-                                // that the retired field is updated to prevent the drop
-                                // implementation from double retiring
-                                if unsafe {
-                                    (*wrapper.inner)
-                                        .retired
-                                        .compare_exchange(
-                                            false,
-                                            true,
-                                            Ordering::SeqCst,
-                                            Ordering::SeqCst,
-                                        )
-                                        .is_ok()
-                                } {
-                                    wrapper.retire();
-                                }
-                            }
-                            self.loop_insert(new_descriptor_guard.data);
-                            // we now check whether the operation was actually successful
-                            if unsafe {
-                                (*new_descriptor_guard.data).success.load(Ordering::SeqCst)
-                            } {
-                                std::mem::drop(pending_holder_guard);
-                                std::mem::drop(new_descriptor_guard);
-                                std::mem::drop(current_node_guard);
-                                HazPtrHolder::try_reclaim();
-                                break;
-                            } else {
-                                std::mem::drop(pending_holder_guard);
-                                std::mem::drop(current_node_guard);
-                                std::mem::drop(new_descriptor_guard);
-                                HazPtrHolder::try_reclaim();
-                                // loop back as the operation failed at a later stage
-                                continue;
-                            }
-                        } else {
-                            std::mem::drop(new_descriptor_guard);
-                            std::mem::drop(current_node_guard);
-                            let _ = unsafe { Box::from_raw(new_descriptor) };
-                            self.help(thing.data);
-                            std::mem::drop(pending_holder_guard);
-                            HazPtrHolder::try_reclaim();
-                        }
-                    } else {
-                        std::mem::drop(new_descriptor_guard);
-                        std::mem::drop(current_node_guard);
-                        let _ = unsafe { Box::from_raw(new_descriptor) };
-                        self.help(thing.data);
-                        std::mem::drop(pending_holder_guard);
-                        HazPtrHolder::try_reclaim();
-                    }
-                }
+            if count > 1 {
+                // The helpers only ever write the index of the first node, the rest of the chain
+                // is not theirs to touch once it may be in the list. Nobody else has seen it yet.
+                let index = unsafe { (*current_node).index.load(Ordering::Acquire) };
+                Self::number(first, count, index.wrapping_add(1));
+            }
+            let new_descriptor: *mut Descriptor<T> = self.alloc(
+                Descriptor::new(
+                    current_node,
+                    next,
+                    core::ptr::null_mut(),
+                    Operation::Insert,
+                    self.deleter(),
+                    announced,
+                )
+                .chain(last, count),
+            );
+            let mut new_descriptor_holder = HazPtrHolder::default();
+            let mut new_descriptor_guard = unsafe {
+                new_descriptor_holder
+                    .load(&AtomicPtr::new(new_descriptor))
+                    .expect("Has to be there")
+            };
+            if !self.install(&self.head_descriptor, new_descriptor) {
+                // nobody else has seen the descriptor yet so it can be thrown away right here
+                core::mem::drop(new_descriptor_guard);
+                core::mem::drop(current_node_guard);
+                self.discard(new_descriptor);
+                HazPtrHolder::try_reclaim();
+                self.backoff.snooze(step);
+                step = step.saturating_add(1);
+                continue;
+            }
+            self.loop_insert(new_descriptor_guard.data);
+            // we now check whether the operation was actually successful
+            if unsafe { (*new_descriptor_guard.data).success.load(Ordering::Acquire) } {
+                return Inserted::Linked;
+            }
+            if unsafe { (*new_descriptor_guard.data).status.load(Ordering::Acquire) } == REJECTED {
+                return Inserted::Rejected;
+            }
+            if announced.is_some() {
+                // the head may have become null in the meantime, which the caller handles
+                return Inserted::Empty;
+            }
+            core::mem::drop(new_descriptor_guard);
+            core::mem::drop(current_node_guard);
+            HazPtrHolder::try_reclaim();
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+            // loop back as the operation failed at a later stage
+        }
+    }
+
+    // Gives the count nodes of a chain that is not in the list yet the indices they will have once
+    // it is, starting from the oldest.
+    pub(crate) fn number(first: *mut Node<T>, count: usize, start: usize) {
+        let mut node = first;
+        for i in 0..count {
+            unsafe {
+                (*node)
+                    .index
+                    .store(start.wrapping_add(i), Ordering::Relaxed);
+                node = (*node).prev.load(Ordering::Relaxed);
             }
         }
     }
 
-    // The swap_null function is called when we find that the pointer to the descriptor is null.
-    // The function tries to compare and exchange expecting a null pointer which if succeeds we
-    // initiate the recurive call and if fails we move forward to see if there is anyone we can
-    // help before looping back.
-    fn swap_null_insert(&self, new_descriptor: *mut Descriptor<T>) -> SwapResult {
-        let mut new_descriptor_holder = HazPtrHolder::default();
-        let mut new_descriptor_guard = unsafe {
-            new_descriptor_holder
-                .load(&AtomicPtr::new(new_descriptor))
-                .expect("Has to be there")
+    // Moves the value back out of a node that some insert descriptor was about to link but turned
+    // away because the list was full. Its helpers may still hold the node, so it is retired like
+    // any other instead of being thrown away.
+    pub(crate) fn take_back(&self, node: *mut Node<T>) -> T {
+        let value = unsafe { core::ptr::read(&*(*node).value) };
+        unsafe { (*node).value_moved.store(true, Ordering::Release) };
+        self.retire(node);
+        value
+    }
+
+    fn retire(&self, node: *mut Node<T>) {
+        let mut holder = HazPtrHolder::default();
+        let wrapper =
+            unsafe { holder.swap(&AtomicPtr::new(node), core::ptr::null_mut(), self.deleter()) };
+        if let Some(mut wrapper) = wrapper {
+            wrapper.defer_retire();
+        }
+    }
+
+    // Moves the value out of a node that a delete of ours detached, unless a remove took it first.
+    // The delete claimed it for us and nobody else moves it out. The caller either still protects
+    // the node or is the only one to retire it, so it can not be reclaimed underneath us.
+    unsafe fn taken(node: *mut Node<T>) -> Option<T> {
+        (unsafe { (*node).state.load(Ordering::Acquire) } == TAKEN)
+            .then(|| unsafe { (*node).take() })
+    }
+
+    // Whether linking count more nodes in front of head would take the list past its capacity. Only
+    // asked while an insert holds the head descriptor, so head can not move and no delete of the
+    // last node is halfway done. A null tail means the first node of the list is still being linked
+    // into the empty list and got the index zero.
+    // The removes are counted after the tail was read, so every node the tail got past without
+    // a value is counted as removed as well, and at worst a value the tail moved past since still
+    // takes up room.
+    fn full(&self, head: *mut Node<T>, count: usize) -> bool {
+        if self.capacity == usize::MAX {
+            return false;
+        }
+        let head_index = unsafe { (*head).index.load(Ordering::Acquire) };
+        let mut tail_holder = HazPtrHolder::default();
+        let tail_guard = unsafe { tail_holder.load(&self.tail) };
+        let (tail_index, skipped) = match tail_guard {
+            Some(ref guard) => unsafe {
+                (
+                    guard.index.load(Ordering::Acquire),
+                    guard.skipped.load(Ordering::Acquire),
+                )
+            },
+            None => (0, self.skipped.load(Ordering::SeqCst)),
         };
-        // respecting stack frames...therefore loading it into hazard pointers
-        let mut current_node_holder = HazPtrHolder::default();
-        let mut current_node_guard = unsafe {
-            current_node_holder.load(&AtomicPtr::new((*new_descriptor_guard.data).current))
+        let removed = self.removed.load(Ordering::SeqCst);
+        let nodes = head_index.wrapping_sub(tail_index) + 1;
+        nodes.saturating_sub(removed - skipped) + count > self.capacity
+    }
+
+    // Publishes a descriptor in one of the two slots. The slot only counts as free once the
+    // descriptor in it is not pending anymore, otherwise we help that one finish and return false
+    // so that the caller can start over with a fresh look at the list. Whoever manages to replace
+    // a descriptor is the one that retires it.
+    fn install(&self, slot: &AtomicPtr<Descriptor<T>>, new: *mut Descriptor<T>) -> bool {
+        let mut old_holder = HazPtrHolder::default();
+        let mut old_guard = unsafe { old_holder.load(slot) };
+        let old = if let Some(ref mut thing) = old_guard {
+            if unsafe { (*thing.data).pending.load(Ordering::Acquire) } {
+                self.help(thing.data);
+                return false;
+            }
+            thing.data
+        } else {
+            core::ptr::null_mut()
         };
-        if self
-            .descriptor
-            .compare_exchange(
-                std::ptr::null_mut(),
-                new_descriptor_guard.data,
-                Ordering::SeqCst,
-                Ordering::SeqCst,
-            )
-            .is_ok()
+        if slot
+            .compare_exchange(old, new, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
         {
-            self.loop_insert(new_descriptor_guard.data);
-            if unsafe { (*new_descriptor_guard.data).success.load(Ordering::Acquire) } {
-                std::mem::drop(new_descriptor_guard);
-                std::mem::drop(current_node_guard);
-                HazPtrHolder::try_reclaim();
-                return SwapResult::Success;
-            } else {
-                std::mem::drop(new_descriptor_guard);
-                std::mem::drop(current_node_guard);
-                HazPtrHolder::try_reclaim();
-                return SwapResult::Failure;
+            return false;
+        }
+        if !old.is_null() {
+            let mut swap_holder = HazPtrHolder::default();
+            let wrapper = unsafe {
+                swap_holder.swap(&AtomicPtr::new(old), core::ptr::null_mut(), (*old).deleter)
+            };
+            // this code path ensures that the descriptor is retired only once but double
+            // retirement can arise due to the drop implementation therefore we still have to
+            // update the retired field
+            if let Some(mut wrapper) = wrapper
+                && unsafe {
+                    (*wrapper.inner)
+                        .retired
+                        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                        .is_ok()
+                }
+            {
+                wrapper.defer_retire();
+            }
+        }
+        true
+    }
+
+    // An insert stores the new node into the prev field of the old head before it moves the head,
+    // so a delete can find the prev of its node already set while the insert that set it is still
+    // pending, and the helpers of that insert still write into the node. The node must not be
+    // retired before they are done, so we finish the insert first. This only ever happens to the
+    // node right behind the head, deletes further down never wait for the head side.
+    fn settle(&self, node: *mut Node<T>) {
+        let mut holder = HazPtrHolder::default();
+        let mut guard = unsafe { holder.load(&self.head_descriptor) };
+        if let Some(ref mut thing) = guard {
+            let descriptor = unsafe { &*thing.data };
+            if matches!(descriptor.op, Operation::Insert)
+                && descriptor.current == node
+                && descriptor.pending.load(Ordering::Acquire)
+            {
+                self.loop_insert(thing.data);
             }
-        } else {
-            return SwapResult::Failure;
+        }
+    }
+
+    // Gives the first node linked into an empty list the skipped count the list ended up with
+    // before its tail points at the node. Whatever emptied the head left the count behind first,
+    // and the CAS that found the head null reads from it.
+    pub(crate) fn start_over(&self, first: *mut Node<T>) {
+        unsafe {
+            (*first)
+                .skipped
+                .store(self.skipped.load(Ordering::SeqCst), Ordering::Release)
+        };
+    }
+
+    // Finishes whatever operation is pending in the head descriptor, for readers that ran into the
+    // list halfway through the delete of its last node.
+    pub(crate) fn help_head(&self) {
+        let mut holder = HazPtrHolder::default();
+        let mut guard = unsafe { holder.load(&self.head_descriptor) };
+        if let Some(ref mut thing) = guard
+            && unsafe { (*thing.data).pending.load(Ordering::Acquire) }
+        {
+            self.help(thing.data);
         }
     }
 
@@ -296,9 +530,315 @@ impl<T> LinkedList<T> {
             Operation::Delete => {
                 self.loop_delete(current_descriptor);
             }
+            Operation::Close => {
+                self.loop_close(current_descriptor);
+            }
+            Operation::Drain => {
+                self.loop_drain(current_descriptor);
+            }
+            Operation::Hold => {
+                self.loop_hold(current_descriptor);
+            }
+            Operation::Unlink => {
+                self.loop_unlink(current_descriptor);
+            }
         }
     }
 
+    // Closes the list from within the head descriptor. Every insert that went through the head
+    // descriptor before has finished by the time the flag is set and every one after it sees the
+    // flag when it decides whether to link its node. Inserts into the empty list do not go through
+    // the descriptor and look at the flag themselves once they linked their node.
+    pub(crate) fn seal(&self) {
+        let mut step = 0;
+        loop {
+            let new: *mut Descriptor<T> = self.alloc(Descriptor::new(
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+                core::ptr::null_mut(),
+                Operation::Close,
+                self.deleter(),
+                None,
+            ));
+            let mut new_holder = HazPtrHolder::default();
+            let mut new_guard = unsafe {
+                new_holder
+                    .load(&AtomicPtr::new(new))
+                    .expect("Has to be there")
+            };
+            if self.install(&self.head_descriptor, new) {
+                self.loop_close(new_guard.data);
+                return;
+            }
+            core::mem::drop(new_guard);
+            self.discard(new);
+            HazPtrHolder::try_reclaim();
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    fn loop_close(&self, current_descriptor: *mut Descriptor<T>) {
+        self.closed.store(true, Ordering::SeqCst);
+        // see link for the other half of this fence
+        fence(Ordering::SeqCst);
+        unsafe {
+            (*current_descriptor)
+                .pending
+                .store(false, Ordering::Release)
+        };
+    }
+
+    // Takes every node out of the list at once. Returns the oldest node, the chain runs from there
+    // through the prev pointers up to the node that was the head, whose prev is null, or None if
+    // the list was empty. The indices only give an upper bound on its length, a node unlinked from
+    // the middle of the list leaves a gap in them. The drain sits in the head descriptor, which
+    // keeps the inserts and the delete of the last node out, and gets the tail descriptor as well
+    // through a Hold descriptor pointing back at it, which keeps the other deletes out. With both
+    // ends held the tail and then the head are emptied, and the list is empty for everybody from
+    // then on.
+    pub(crate) fn drain(&self) -> Option<*mut Node<T>> {
+        let mut step = 0;
+        // a descriptor that never got published is pointed at the new ends and tried again
+        let mut spare: *mut Descriptor<T> = core::ptr::null_mut();
+        loop {
+            let mut tail_holder = HazPtrHolder::default();
+            let tail_guard = unsafe { tail_holder.load(&self.tail) };
+            let mut head_holder = HazPtrHolder::default();
+            let head_guard = unsafe { head_holder.load(&self.head) };
+            match (&tail_guard, &head_guard) {
+                (None, None) => {
+                    if !spare.is_null() {
+                        self.discard(spare);
+                    }
+                    return None;
+                }
+                (Some(tail), Some(head)) => {
+                    let count = head
+                        .index
+                        .load(Ordering::Acquire)
+                        .wrapping_sub(tail.index.load(Ordering::Acquire))
+                        .wrapping_add(1);
+                    let new: *mut Descriptor<T> = if spare.is_null() {
+                        self.alloc(Descriptor::new(
+                            tail.data,
+                            core::ptr::null_mut(),
+                            core::ptr::null_mut(),
+                            Operation::Drain,
+                            self.deleter(),
+                            None,
+                        ))
+                    } else {
+                        unsafe { (*spare).current = tail.data };
+                        spare
+                    };
+                    unsafe {
+                        (*new).last = head.data;
+                        (*new).count = count;
+                    }
+                    spare = core::ptr::null_mut();
+                    let mut new_holder = HazPtrHolder::default();
+                    let mut new_guard = unsafe {
+                        new_holder
+                            .load(&AtomicPtr::new(new))
+                            .expect("Has to be there")
+                    };
+                    if self.install(&self.head_descriptor, new) {
+                        self.loop_drain(new_guard.data);
+                        if unsafe { (*new_guard.data).success.load(Ordering::Acquire) } {
+                            return Some(tail.data);
+                        }
+                    } else {
+                        spare = new;
+                    }
+                }
+                // the last node is halfway through its delete, another drain is halfway done or a
+                // node is being linked into the empty list and does not have its tail yet
+                _ => self.help_head(),
+            }
+            core::mem::drop(head_guard);
+            core::mem::drop(tail_guard);
+            HazPtrHolder::try_reclaim();
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    fn loop_drain(&self, current_descriptor: *mut Descriptor<T>) {
+        let mut descriptor_holder = HazPtrHolder::default();
+        let Some(descriptor_guard) =
+            (unsafe { descriptor_holder.load(&AtomicPtr::new(current_descriptor)) })
+        else {
+            return;
+        };
+        let descriptor = unsafe { &*descriptor_guard.data };
+        // both ends are protected before we look at the status, so that neither can be reclaimed
+        // and come back as a new node before a late CAS of ours
+        let mut tail_holder = HazPtrHolder::default();
+        let tail_guard = unsafe { tail_holder.load(&AtomicPtr::new(descriptor.current)) };
+        let mut head_holder = HazPtrHolder::default();
+        let head_guard = unsafe { head_holder.load(&AtomicPtr::new(descriptor.last)) };
+        let mut step = 0;
+        // kept for the next try as long as it was not published
+        let mut hold: *mut Descriptor<T> = core::ptr::null_mut();
+        while descriptor.pending.load(Ordering::Acquire) {
+            match descriptor.status.load(Ordering::Acquire) {
+                0 => {
+                    let mut slot_holder = HazPtrHolder::default();
+                    let slot_guard = unsafe { slot_holder.load(&self.tail_descriptor) };
+                    let held = match slot_guard {
+                        Some(ref thing)
+                            if unsafe { (*thing.data).pending.load(Ordering::Acquire) } =>
+                        {
+                            if unsafe { (*thing.data).partner } != current_descriptor {
+                                self.help(thing.data);
+                                continue;
+                            }
+                            true
+                        }
+                        _ => false,
+                    };
+                    core::mem::drop(slot_guard);
+                    if !held {
+                        if hold.is_null() {
+                            hold = self.alloc(
+                                Descriptor::new(
+                                    core::ptr::null_mut(),
+                                    core::ptr::null_mut(),
+                                    core::ptr::null_mut(),
+                                    Operation::Hold,
+                                    self.deleter(),
+                                    None,
+                                )
+                                .hold(current_descriptor),
+                            );
+                        }
+                        if self.install(&self.tail_descriptor, hold) {
+                            hold = core::ptr::null_mut();
+                        } else {
+                            self.backoff.snooze(step);
+                            step = step.saturating_add(1);
+                        }
+                        continue;
+                    }
+                    // Nothing moves either end while we hold both descriptors, so whatever we
+                    // find here stays true until the status is decided. An insert that finished
+                    // before we got the head descriptor may have linked a node in front of the
+                    // head after the initiator looked, just like for the delete of the last node.
+                    let intact = self.tail.load(Ordering::Acquire) == descriptor.current
+                        && self.head.load(Ordering::Acquire) == descriptor.last
+                        && unsafe { (*descriptor.last).prev.load(Ordering::Acquire) }.is_null();
+                    let decision = if intact { 1 } else { REJECTED };
+                    descriptor.status.compare_exchange(
+                        0,
+                        decision,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    );
+                }
+                1 => {
+                    // Every helper claims the values that are still there before the ends move,
+                    // so no remove that walks into the chain afterwards can take one, and counts
+                    // the nodes whose value went to a remove already, together with the gaps
+                    // unlinks left in the indices, as skipped for the next node linked into the
+                    // empty list. The first claim on a node decides it for everybody, so they all
+                    // count the same and nobody waits for another helper to finish. No node of
+                    // the chain can be retired before the status moves on, so one we protect
+                    // while it is still 1 stays around for as long as we look at it.
+                    let mut node_holder = HazPtrHolder::default();
+                    let mut node = descriptor.current;
+                    let mut removed = 0;
+                    let mut nodes = 0;
+                    let mut frozen = true;
+                    while !node.is_null() {
+                        unsafe { node_holder.load(&AtomicPtr::new(node)) }
+                            .expect("Has to be there")
+                            .keep();
+                        if descriptor.status.load(Ordering::Acquire) != 1 {
+                            frozen = false;
+                            break;
+                        }
+                        match unsafe { (*node).claim(TAKEN) } {
+                            Claim::Won | Claim::Taken => {}
+                            Claim::Removed => removed += 1,
+                            // a remove is asking its predicate about the value
+                            Claim::Busy => {
+                                sync::spin_loop();
+                                self.backoff.snooze(step);
+                                step = step.saturating_add(1);
+                                continue;
+                            }
+                        }
+                        nodes += 1;
+                        node = unsafe { (*node).prev.load(Ordering::Acquire) };
+                    }
+                    core::mem::drop(node_holder);
+                    if !frozen {
+                        continue;
+                    }
+                    let skipped = unsafe { (*descriptor.current).skipped.load(Ordering::Acquire) }
+                        + removed
+                        + (descriptor.count - nodes);
+                    self.skipped.fetch_max(skipped, Ordering::SeqCst);
+                    // the tail goes first, a null head would let an insert into the empty list
+                    // store a tail of its own
+                    self.tail.compare_exchange(
+                        descriptor.current,
+                        core::ptr::null_mut(),
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    );
+                    self.head.compare_exchange(
+                        descriptor.last,
+                        core::ptr::null_mut(),
+                        Ordering::SeqCst,
+                        Ordering::Acquire,
+                    );
+                    descriptor
+                        .status
+                        .compare_exchange(1, 2, Ordering::AcqRel, Ordering::Acquire);
+                }
+                2 => {
+                    descriptor.success.store(true, Ordering::Release);
+                    descriptor.pending.store(false, Ordering::Release);
+                }
+                _ => descriptor.pending.store(false, Ordering::Release),
+            }
+        }
+        if !hold.is_null() {
+            self.discard(hold);
+        }
+        // Every helper lets go of the Hold on the way out, a late one may have installed it after
+        // the drain was done already. A Hold that stayed pending past the reclamation of its drain
+        // would be taken for its own by the next drain that gets the same address.
+        let mut slot_holder = HazPtrHolder::default();
+        if let Some(ref thing) = unsafe { slot_holder.load(&self.tail_descriptor) }
+            && unsafe { (*thing.data).partner } == current_descriptor
+        {
+            unsafe { (*thing.data).pending.store(false, Ordering::Release) };
+        }
+    }
+
+    // A Hold is done once its drain is, it only ever helps the drain along. The drain can only be
+    // reclaimed once another descriptor took its place in the head descriptor, so finding it there
+    // after protecting it means it is still around.
+    fn loop_hold(&self, current_descriptor: *mut Descriptor<T>) {
+        let mut descriptor_holder = HazPtrHolder::default();
+        let Some(descriptor_guard) =
+            (unsafe { descriptor_holder.load(&AtomicPtr::new(current_descriptor)) })
+        else {
+            return;
+        };
+        let descriptor = unsafe { &*descriptor_guard.data };
+        let mut partner_holder = HazPtrHolder::default();
+        let partner_guard = unsafe { partner_holder.load(&AtomicPtr::new(descriptor.partner)) };
+        if self.head_descriptor.load(Ordering::SeqCst) == descriptor.partner {
+            self.loop_drain(descriptor.partner);
+        }
+        core::mem::drop(partner_guard);
+        descriptor.pending.store(false, Ordering::Release);
+    }
+
     // note down later why the recursive approach did not work and had to switch to loop based
     // approach
     fn loop_insert(&self, current_descriptor: *mut Descriptor<T>) {
@@ -314,7 +854,7 @@ impl<T> LinkedList<T> {
             unsafe {
                 (*actual_descriptor_guard.data)
                     .pending
-                    .store(false, Ordering::SeqCst);
+                    .store(false, Ordering::Release);
             }
             return;
         }
@@ -338,38 +878,73 @@ impl<T> LinkedList<T> {
         let current = if let Some(ref mut guard) = head_ptr_guard {
             guard.data
         } else {
-            std::ptr::null_mut()
+            core::ptr::null_mut()
         };
         let pending = unsafe { &(*actual_descriptor_guard.data).pending };
         let status = unsafe { &(*actual_descriptor_guard.data).status };
         loop {
-            match pending.load(Ordering::SeqCst) {
-                true => match status.load(Ordering::SeqCst) {
+            match pending.load(Ordering::Acquire) {
+                true => match status.load(Ordering::Acquire) {
                     1 => {
+                        unsafe {
+                            (*actual_descriptor_guard.data)
+                                .success
+                                .store(true, Ordering::Release);
+                        }
+                        // every helper writes the same index, current is fixed and its own index
+                        // was written before it became the head
+                        unsafe {
+                            (*next).index.store(
+                                (*current).index.load(Ordering::Acquire).wrapping_add(1),
+                                Ordering::Release,
+                            )
+                        };
                         Self::insert_head(next, current);
                         head_ptr.compare_exchange(
                             current,
-                            next,
-                            Ordering::SeqCst,
-                            Ordering::SeqCst,
+                            unsafe { (*actual_descriptor_guard.data).last },
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
                         );
-                        pending.store(false, Ordering::SeqCst);
+                        unsafe { (*actual_descriptor_guard.data).finish_announced() };
+                        pending.store(false, Ordering::Release);
                         break;
                     }
                     0 => {
-                        let now = head_ptr.load(Ordering::SeqCst);
-                        if now != current {
-                            pending.store(false, Ordering::SeqCst);
+                        let now = head_ptr.load(Ordering::Acquire);
+                        let descriptor = unsafe { &*actual_descriptor_guard.data };
+                        // in wait-free mode the node may already be in the list, either through
+                        // another descriptor for the same announcement or through its owner
+                        // linking it into an empty list, which it marks as busy while doing so
+                        if now != current
+                            || now == descriptor.last
+                            || (!descriptor.announcement.is_null()
+                                && unsafe {
+                                    (*descriptor.announcement).busy.load(Ordering::Acquire)
+                                })
+                            || descriptor.announced_finished()
+                        {
+                            pending.store(false, Ordering::Release);
                             return;
                         }
-                        unsafe {
-                            (*actual_descriptor_guard.data)
-                                .success
-                                .store(true, Ordering::SeqCst);
-                        }
-                        status.compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst);
+                        let decision = if self.closed.load(Ordering::SeqCst)
+                            || self.full(current, descriptor.count)
+                        {
+                            REJECTED
+                        } else {
+                            1
+                        };
+                        status.compare_exchange(0, decision, Ordering::AcqRel, Ordering::Acquire);
                         continue;
                     }
+                    REJECTED => {
+                        let descriptor = unsafe { &*actual_descriptor_guard.data };
+                        if !descriptor.announcement.is_null() {
+                            unsafe { (*descriptor.announcement).reject(descriptor.phase) };
+                        }
+                        pending.store(false, Ordering::Release);
+                        return;
+                    }
                     _ => unreachable!(),
                 },
                 false => return,
@@ -394,182 +969,477 @@ impl<T> LinkedList<T> {
         if guard.is_none() {
             return;
         }
+        // Only ever from null, a late helper must not point the old head at the new node again
+        // once an unlink took that one back out of the middle of the list.
         unsafe {
-            (&(*old).prev).store(new, Ordering::SeqCst);
-        }
+            (*old).prev.compare_exchange(
+                core::ptr::null_mut(),
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+        };
     }
 
-    pub(crate) fn delete(&self) -> Option<T> {
+    // In wait-free mode it works for an announced delete and returns None, the value goes to the
+    // announcement.
+    pub(crate) fn delete(&self, announced: Option<(&Announcement<T>, usize)>) -> Option<T> {
+        let mut step = 0;
         loop {
+            if let Some((announcement, phase)) = announced
+                && announcement.finished(phase)
+            {
+                return None;
+            }
             let mut current_node_holder = HazPtrHolder::default();
             let mut current_node_guard = unsafe { current_node_holder.load(&self.tail) };
-            if current_node_guard.is_none() {
-                //println!("should not be printed for the current test case");
+            // an empty list has nothing to delete. An announced delete still has to be finished
+            // through a descriptor though, so that finding the list empty is decided in the same
+            // order as the operations that work for it in the other slot
+            if current_node_guard.is_none() && announced.is_none() {
                 return None;
             }
-            let mut actual_current_node_guard = current_node_guard.expect("Has to be there");
-            let uninit = Box::into_raw(Box::new(MaybeUninit::uninit()));
-            let new = Box::into_raw(Box::new(Descriptor::new(
-                actual_current_node_guard.data,
-                AtomicBool::new(false),
-                std::ptr::null_mut(),
-                AtomicUsize::new(0),
-                AtomicBool::new(true),
+            let current_node = current_node_guard
+                .as_ref()
+                .map_or(core::ptr::null_mut(), |guard| guard.data);
+            let prev = if current_node.is_null() {
+                core::ptr::null_mut()
+            } else {
+                unsafe { (*current_node).prev.load(Ordering::Acquire) }
+            };
+            // As long as there is a node in front of ours the delete only moves the tail and goes
+            // through the tail descriptor, next to whatever the inserts are doing at the head. The
+            // last node is different, taking it out empties the head as well, so that delete goes
+            // through the head descriptor and keeps the inserts out until it is done.
+            let slot = if prev.is_null() {
+                &self.head_descriptor
+            } else {
+                self.settle(current_node);
+                &self.tail_descriptor
+            };
+            let new: *mut Descriptor<T> = self.alloc(Descriptor::new(
+                current_node,
+                core::ptr::null_mut(),
+                prev,
                 Operation::Delete,
-                &DELETER1,
-                AtomicBool::new(false),
-                AtomicPtr::new(uninit),
-                AtomicBool::new(false),
-            )));
-            if self.descriptor.load(Ordering::Acquire).is_null() {
-                let mut new_descriptor_holder = HazPtrHolder::default();
-                let mut new_descriptor_guard = unsafe {
-                    new_descriptor_holder
-                        .load(&AtomicPtr::new(new))
-                        .expect("Has to be there")
-                };
-                if self
-                    .descriptor
-                    .compare_exchange(
-                        std::ptr::null_mut(),
-                        new,
-                        Ordering::SeqCst,
-                        Ordering::SeqCst,
-                    )
-                    .is_ok()
+                self.deleter(),
+                announced,
+            ));
+            let mut new_holder = HazPtrHolder::default();
+            let mut new_guard = unsafe {
+                new_holder
+                    .load(&AtomicPtr::new(new))
+                    .expect("Has to be there")
+            };
+            if !self.install(slot, new) {
+                core::mem::drop(new_guard);
+                core::mem::drop(current_node_guard);
+                self.discard(new);
+                HazPtrHolder::try_reclaim();
+                self.backoff.snooze(step);
+                step = step.saturating_add(1);
+                continue;
+            }
+            self.loop_delete(new_guard.data);
+            if unsafe { (*new_guard.data).success.load(Ordering::Acquire) } {
+                // a node whose value went to a remove is only unlinked, the delete carries on
+                // with the next one
+                if !current_node.is_null()
+                    && unsafe { (*current_node).state.load(Ordering::Acquire) } == REMOVED
                 {
-                    self.loop_delete(new_descriptor_guard.data);
-                    if unsafe { (*new_descriptor_guard.data).success.load(Ordering::SeqCst) } {
-                        // possibly redundant but fine
-                        if unsafe {
-                            (*new_descriptor_guard.data)
-                                .init_stored
-                                .load(Ordering::SeqCst)
-                        } {
-                            let init_ptr = unsafe {
-                                (*new_descriptor_guard.data)
-                                    .taken_value
-                                    .swap(std::ptr::null_mut(), Ordering::SeqCst)
-                            };
-                            let owned_init = unsafe { Box::from_raw(init_ptr) };
-                            let taken_value = unsafe { owned_init.assume_init() };
-
-                            std::mem::drop(new_descriptor_guard);
-                            std::mem::drop(actual_current_node_guard);
-                            HazPtrHolder::try_reclaim();
-                            return Some(*taken_value);
-                        } else {
-                            std::mem::drop(new_descriptor_guard);
-                            std::mem::drop(actual_current_node_guard);
-                            HazPtrHolder::try_reclaim();
-                            return None;
-                        }
+                    continue;
+                }
+                if announced.is_none() {
+                    return unsafe { Self::taken(current_node) };
+                }
+            }
+            core::mem::drop(new_guard);
+            core::mem::drop(current_node_guard);
+            HazPtrHolder::try_reclaim();
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    // Deletes the tail only if the predicate holds for its value, which is asked while the delete
+    // sits in its descriptor and nobody can take the node away, so the value it looks at can not
+    // be moved out and dropped underneath it. Everybody who comes across the descriptor waits for
+    // the answer, so unlike every other operation this one can hold up the others while the
+    // predicate runs.
+    pub(crate) fn delete_if(
+        &self,
+        predicate: &mut dyn FnMut(&T) -> bool,
+    ) -> Result<T, DeleteIfError> {
+        let mut step = 0;
+        loop {
+            let mut current_node_holder = HazPtrHolder::default();
+            let Some(current_node_guard) = (unsafe { current_node_holder.load(&self.tail) }) else {
+                return Err(DeleteIfError::Empty);
+            };
+            let current_node = current_node_guard.data;
+            let prev = current_node_guard.prev.load(Ordering::Acquire);
+            // the same choice of descriptor as for any other delete
+            let slot = if prev.is_null() {
+                &self.head_descriptor
+            } else {
+                self.settle(current_node);
+                &self.tail_descriptor
+            };
+            let new: *mut Descriptor<T> = self.alloc(
+                Descriptor::new(
+                    current_node,
+                    core::ptr::null_mut(),
+                    prev,
+                    Operation::Delete,
+                    self.deleter(),
+                    None,
+                )
+                .conditional(),
+            );
+            let mut new_holder = HazPtrHolder::default();
+            let mut new_guard = unsafe {
+                new_holder
+                    .load(&AtomicPtr::new(new))
+                    .expect("Has to be there")
+            };
+            if !self.install(slot, new) {
+                core::mem::drop(new_guard);
+                core::mem::drop(current_node_guard);
+                self.discard(new);
+                HazPtrHolder::try_reclaim();
+                self.backoff.snooze(step);
+                step = step.saturating_add(1);
+                continue;
+            }
+            let descriptor = unsafe { &*new_guard.data };
+            // A delete of our node that got its descriptor before us is done by now and moved
+            // the tail on. One that comes later waits in line behind us, or went for the other
+            // descriptor and gives up once it sees the prev field, which stays as we found it
+            // while we hold ours.
+            let ours = self.tail.load(Ordering::SeqCst) == current_node
+                && (!prev.is_null() || current_node_guard.prev.load(Ordering::SeqCst).is_null());
+            let mut declined = false;
+            match ours.then(|| current_node_guard.claim(list::ASKING)) {
+                Some(Claim::Won) => {
+                    // a predicate that panics leaves the delete declined instead of holding up
+                    // everybody else for good. The claim keeps a remove away from the value while
+                    // the predicate looks at it and goes back before the delete takes the value
+                    let decline = Decline(&descriptor.approval);
+                    let unclaim = Unclaim(&current_node_guard.state);
+                    let approved = predicate(unsafe { &*current_node_guard.value });
+                    core::mem::drop(unclaim);
+                    if approved {
+                        descriptor.approval.store(APPROVED, Ordering::Release);
                     } else {
-                        std::mem::drop(new_descriptor_guard);
-                        std::mem::drop(actual_current_node_guard);
-                        HazPtrHolder::try_reclaim();
-                        continue;
+                        declined = true;
                     }
+                    core::mem::drop(decline);
                 }
-            } else {
-                let mut descriptor_holder = HazPtrHolder::default();
-                let mut descriptor_guard = unsafe { descriptor_holder.load(&self.descriptor) };
-                if let Some(ref mut thing) = descriptor_guard {
-                    let mut new_holder = HazPtrHolder::default();
-                    let mut new_guard = unsafe {
-                        new_holder
-                            .load(&AtomicPtr::new(new))
-                            .expect("Has to be there")
+                // the value is gone already, but the node still has to go
+                Some(Claim::Removed) => descriptor.approval.store(APPROVED, Ordering::Release),
+                _ => descriptor.approval.store(DECLINED, Ordering::Release),
+            }
+            self.loop_delete(new_guard.data);
+            if declined {
+                return Err(DeleteIfError::Rejected);
+            }
+            if unsafe { (*new_guard.data).success.load(Ordering::Acquire) } {
+                // a remove may have taken the value after the predicate looked at it, in which
+                // case the node went without one and the next tail gets asked
+                match unsafe { Self::taken(current_node) } {
+                    Some(value) => return Ok(value),
+                    None => continue,
+                }
+            }
+            core::mem::drop(new_guard);
+            core::mem::drop(current_node_guard);
+            HazPtrHolder::try_reclaim();
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    // Detaches up to n nodes from the tail with a single descriptor and appends their values to
+    // out, oldest first. A batch never takes the last node, emptying the list touches the head as
+    // well and is left to delete. Returns how many nodes were detached, zero if the tail has no
+    // node in front of it.
+    pub(crate) fn delete_chain(&self, n: usize, out: &mut Vec<T>) -> usize {
+        let mut step = 0;
+        loop {
+            // read before the walk, the descriptor gives up if anything got unlinked since
+            let unlinks = self.unlinks.load(Ordering::SeqCst);
+            let mut current_node_holder = HazPtrHolder::default();
+            let Some(current_node_guard) = (unsafe { current_node_holder.load(&self.tail) }) else {
+                return 0;
+            };
+            let current_node = current_node_guard.data;
+            let mut last = current_node;
+            let mut prev = current_node_guard.prev.load(Ordering::Acquire);
+            if prev.is_null() || n == 0 {
+                return 0;
+            }
+            // Every node we walk over is protected before we read its prev, and only counts as
+            // alive if the tail is still where it was and the node we came from is still linked
+            // and still points at it, the same as for the walk of a remove.
+            let mut count = 1;
+            let mut last_holder = HazPtrHolder::default();
+            let mut walk_holder = HazPtrHolder::default();
+            let mut moved = false;
+            while count < n {
+                let node = unsafe { walk_holder.load(&AtomicPtr::new(prev)) }
+                    .expect("Has to be there")
+                    .keep();
+                if self.tail.load(Ordering::SeqCst) != current_node
+                    || unsafe { (*last).unlinked.load(Ordering::SeqCst) }
+                    || unsafe { (*last).prev.load(Ordering::SeqCst) } != node
+                {
+                    moved = true;
+                    break;
+                }
+                let next = unsafe { (*node).prev.load(Ordering::Acquire) };
+                if next.is_null() {
+                    break;
+                }
+                core::mem::swap(&mut last_holder, &mut walk_holder);
+                last = node;
+                prev = next;
+                count += 1;
+            }
+            core::mem::drop(walk_holder);
+            core::mem::drop(last_holder);
+            if moved {
+                core::mem::drop(current_node_guard);
+                self.backoff.snooze(step);
+                step = step.saturating_add(1);
+                continue;
+            }
+            self.settle(last);
+            let new: *mut Descriptor<T> = self.alloc(
+                Descriptor::new(
+                    current_node,
+                    core::ptr::null_mut(),
+                    prev,
+                    Operation::Delete,
+                    self.deleter(),
+                    None,
+                )
+                .chain(last, count)
+                .seen(unlinks),
+            );
+            let mut new_holder = HazPtrHolder::default();
+            let mut new_guard = unsafe {
+                new_holder
+                    .load(&AtomicPtr::new(new))
+                    .expect("Has to be there")
+            };
+            if !self.install(&self.tail_descriptor, new) {
+                core::mem::drop(new_guard);
+                core::mem::drop(current_node_guard);
+                self.discard(new);
+                HazPtrHolder::try_reclaim();
+                self.backoff.snooze(step);
+                step = step.saturating_add(1);
+                continue;
+            }
+            self.loop_delete(new_guard.data);
+            if unsafe { (*new_guard.data).success.load(Ordering::Acquire) } {
+                if count == 1 {
+                    out.extend(unsafe { Self::taken(current_node) });
+                    return count;
+                }
+                // Nobody else retires the nodes of a batch and none of them can be reached from
+                // the list anymore, each prev is read before the node it hangs off is handed over.
+                out.reserve(count);
+                let mut node = current_node;
+                for _ in 0..count {
+                    let next = unsafe { (*node).prev.load(Ordering::Acquire) };
+                    out.extend(unsafe { Self::taken(node) });
+                    self.retire(node);
+                    node = next;
+                }
+                return count;
+            }
+            core::mem::drop(new_guard);
+            core::mem::drop(current_node_guard);
+            HazPtrHolder::try_reclaim();
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    // Takes node out of the middle of the list, given the node behind it and the tail the walk
+    // that found the two started from. Returns false if it was not ours to take out anymore, see
+    // unlinkable.
+    pub(crate) fn unlink(
+        &self,
+        start: *mut Node<T>,
+        behind: *mut Node<T>,
+        node: *mut Node<T>,
+    ) -> bool {
+        let mut step = 0;
+        loop {
+            let prev = unsafe { (*node).prev.load(Ordering::Acquire) };
+            if !self.unlinkable(start, behind, node, prev) {
+                return false;
+            }
+            let new: *mut Descriptor<T> = self.alloc(
+                Descriptor::new(node, behind, prev, Operation::Unlink, self.deleter(), None)
+                    .chain(start, 1),
+            );
+            let mut new_holder = HazPtrHolder::default();
+            let new_guard = unsafe {
+                new_holder
+                    .load(&AtomicPtr::new(new))
+                    .expect("Has to be there")
+            };
+            if self.install(&self.tail_descriptor, new) {
+                self.loop_unlink(new);
+                return unsafe { (*new).success.load(Ordering::Acquire) };
+            }
+            core::mem::drop(new_guard);
+            self.discard(new);
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    // Whether node can be unlinked: the tail is still where the walk started, so neither node nor
+    // the one behind it was deleted, the one behind is still linked and still points at node, and
+    // node is not the head, which the next insert links a node in front of. Once taken from the
+    // tail descriptor none of it can change anymore.
+    fn unlinkable(
+        &self,
+        start: *mut Node<T>,
+        behind: *mut Node<T>,
+        node: *mut Node<T>,
+        prev: *mut Node<T>,
+    ) -> bool {
+        !prev.is_null()
+            && self.tail.load(Ordering::SeqCst) == start
+            && self.head.load(Ordering::SeqCst) != node
+            && unsafe { !(*behind).unlinked.load(Ordering::SeqCst) }
+            && unsafe { (*behind).prev.load(Ordering::SeqCst) } == node
+            && unsafe { (*node).prev.load(Ordering::SeqCst) } == prev
+    }
+
+    // The node keeps its prev, so a walk that stands on it can still get on, but it is marked as
+    // unlinked first so that the walk does not take that for the node still being in the list.
+    fn loop_unlink(&self, current_descriptor: *mut Descriptor<T>) {
+        let mut descriptor_holder = HazPtrHolder::default();
+        let Some(descriptor_guard) =
+            (unsafe { descriptor_holder.load(&AtomicPtr::new(current_descriptor)) })
+        else {
+            return;
+        };
+        let descriptor = unsafe { &*descriptor_guard.data };
+        // both nodes are protected before we look at the status, neither can be retired while
+        // the descriptor is pending
+        let mut node_holder = HazPtrHolder::default();
+        let node_guard = unsafe { node_holder.load(&AtomicPtr::new(descriptor.current)) };
+        let mut behind_holder = HazPtrHolder::default();
+        let behind_guard = unsafe { behind_holder.load(&AtomicPtr::new(descriptor.next)) };
+        while descriptor.pending.load(Ordering::Acquire) {
+            match descriptor.status.load(Ordering::Acquire) {
+                0 => {
+                    let decision = if self.unlinkable(
+                        descriptor.last,
+                        descriptor.next,
+                        descriptor.current,
+                        descriptor.prev,
+                    ) {
+                        1
+                    } else {
+                        REJECTED
                     };
-                    if unsafe { !(*thing.data).pending.load(Ordering::SeqCst) } {
-                        if self
-                            .descriptor
-                            .compare_exchange(thing.data, new, Ordering::SeqCst, Ordering::SeqCst)
+                    descriptor.status.compare_exchange(
+                        0,
+                        decision,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    );
+                }
+                1 => {
+                    unsafe {
+                        (*descriptor.current).unlinked.store(true, Ordering::SeqCst);
+                        (*descriptor.next).prev.compare_exchange(
+                            descriptor.current,
+                            descriptor.prev,
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                        );
+                    }
+                    // Only after our CAS, whoever lets go of the descriptor has been through here
+                    // once the node is out. A batch that read the counter before then walked the
+                    // old chain, and finds it changed when it gets the tail descriptor.
+                    self.unlinks.fetch_add(1, Ordering::SeqCst);
+                    descriptor
+                        .status
+                        .compare_exchange(1, 2, Ordering::AcqRel, Ordering::Acquire);
+                }
+                2 => {
+                    descriptor.success.store(true, Ordering::Release);
+                    descriptor.pending.store(false, Ordering::Release);
+                    if unsafe {
+                        (*descriptor.current)
+                            .retired
+                            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
                             .is_ok()
-                        {
-                            //println!("After descriptor swap");
-                            let mut swap_holder = HazPtrHolder::default();
-                            let mut wrapper = unsafe {
-                                swap_holder.swap(
-                                    &AtomicPtr::new(thing.data),
-                                    std::ptr::null_mut(),
-                                    &DELETER1,
-                                )
-                            };
-                            if let Some(mut wrapper) = wrapper {
-                                if unsafe {
-                                    (*wrapper.inner)
-                                        .retired
-                                        .compare_exchange(
-                                            false,
-                                            true,
-                                            Ordering::SeqCst,
-                                            Ordering::SeqCst,
-                                        )
-                                        .is_ok()
-                                } {
-                                    wrapper.retire();
-                                }
-                            }
-                            self.loop_delete(new);
-                            if unsafe { (*new_guard.data).success.load(Ordering::SeqCst) } {
-                                if unsafe { (*new_guard.data).init_stored.load(Ordering::SeqCst) } {
-                                    let init_ptr = unsafe {
-                                        (*new_guard.data)
-                                            .taken_value
-                                            .swap(std::ptr::null_mut(), Ordering::SeqCst)
-                                    };
-                                    let owned_init = unsafe { Box::from_raw(init_ptr) };
-                                    let taken_value = unsafe { owned_init.assume_init() };
-                                    std::mem::drop(new_guard);
-                                    std::mem::drop(descriptor_guard);
-                                    std::mem::drop(actual_current_node_guard);
-                                    HazPtrHolder::try_reclaim();
-                                    break Some(*taken_value);
-                                } else {
-                                    std::mem::drop(new_guard);
-                                    std::mem::drop(descriptor_guard);
-                                    std::mem::drop(actual_current_node_guard);
-                                    break None;
-                                }
-                            } else {
-                                std::mem::drop(new_guard);
-                                std::mem::drop(descriptor_guard);
-                                std::mem::drop(actual_current_node_guard);
-                                HazPtrHolder::try_reclaim();
-                            }
-                        } else {
-                            let drop = unsafe { Box::from_raw(new) };
-                            std::mem::drop(drop);
-                            self.help(thing.data);
-                            std::mem::drop(descriptor_guard);
-                            std::mem::drop(actual_current_node_guard);
-                            std::mem::drop(new_guard);
-                            HazPtrHolder::try_reclaim();
-                        }
-                    } else {
-                        let drop = unsafe { Box::from_raw(new) };
-                        std::mem::drop(drop);
-                        self.help(thing.data);
-                        std::mem::drop(descriptor_guard);
-                        std::mem::drop(actual_current_node_guard);
-                        std::mem::drop(new_guard);
-                        HazPtrHolder::try_reclaim();
+                    } {
+                        self.retire(descriptor.current);
                     }
                 }
+                _ => descriptor.pending.store(false, Ordering::Release),
             }
         }
     }
 
+    // Writes how many nodes without a value the tail got past into the node it moves to, before
+    // it moves there: the count of the node it leaves, that node or the nodes of the batch that
+    // lost their value to a remove, and the gaps unlinks left in the indices up to the new tail.
+    // A delete that empties the list leaves the count for the next node linked into it, before the
+    // head goes. Every helper writes the same number.
+    fn skip(&self, descriptor: &Descriptor<T>, node: &Node<T>, prev: *mut Node<T>) {
+        let removed = if descriptor.count > 1 {
+            descriptor.removed.load(Ordering::Acquire)
+        } else {
+            usize::from(node.state.load(Ordering::Acquire) == REMOVED)
+        };
+        let skipped = node.skipped.load(Ordering::Acquire) + removed;
+        if prev.is_null() {
+            self.skipped.fetch_max(skipped, Ordering::SeqCst);
+        } else {
+            let gaps = unsafe { (*prev).index.load(Ordering::Acquire) }
+                .wrapping_sub(node.index.load(Ordering::Acquire))
+                - descriptor.count;
+            unsafe { (*prev).skipped.store(skipped + gaps, Ordering::Release) };
+        }
+    }
+
+    // Moves a delete on once the values of its nodes are claimed. The delete of the last node
+    // leaves the skipped count for the next node linked into the empty list and only then empties
+    // the head, so an insert that finds the head null always reads the count it left behind.
+    fn claimed(&self, descriptor: &Descriptor<T>, node: &Node<T>) {
+        if descriptor.prev.is_null() {
+            self.skip(descriptor, node, core::ptr::null_mut());
+            self.head.compare_exchange(
+                descriptor.current,
+                core::ptr::null_mut(),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+        }
+        descriptor
+            .status
+            .compare_exchange(1, 2, Ordering::AcqRel, Ordering::Acquire);
+    }
+
     fn loop_delete(&self, current_descriptor: *mut Descriptor<T>) {
-        //println!("a");
         let mut descriptor_holder = HazPtrHolder::default();
         let mut descriptor_guard =
             unsafe { descriptor_holder.load(&AtomicPtr::new(current_descriptor)) };
         if descriptor_guard.is_none() {
             return;
         }
-        //println!("dg");
         let actual_descriptor_guard = descriptor_guard.expect("Has to be there");
         let tail_ptr = &self.tail;
         let mut tail_ptr_holder = HazPtrHolder::default();
@@ -578,120 +1448,199 @@ impl<T> LinkedList<T> {
             tail_ptr_holder.load(&AtomicPtr::new((*actual_descriptor_guard.data).current))
         };
         if tail_ptr_guard.is_none() {
-            unsafe {
-                (*actual_descriptor_guard.data)
-                    .pending
-                    .store(false, Ordering::SeqCst);
+            if unsafe { (*actual_descriptor_guard.data).announcement.is_null() } {
+                unsafe {
+                    (*actual_descriptor_guard.data)
+                        .pending
+                        .store(false, Ordering::Release);
+                }
+            } else {
+                self.loop_empty(actual_descriptor_guard.data);
             }
             return;
         }
-        //println!("tg");
         let actual_tail_ptr_guard = tail_ptr_guard.expect("Has to be there");
-        //let prev_ptr = unsafe {(*actual_tail_ptr_guard.data).prev.load(Ordering::Acquire)};
-        let mut prev_ptr_holder = HazPtrHolder::default();
-        let mut prev_ptr_guard =
-            unsafe { prev_ptr_holder.load(&(*actual_tail_ptr_guard.data).prev) };
-        let prev = if let Some(ref mut guard) = prev_ptr_guard {
-            guard.data
-        } else {
-            std::ptr::null_mut()
-        };
-        let mut head_ptr_holder = HazPtrHolder::default();
-        let head_ptr = &self.head;
-        let mut head_ptr_guard = unsafe { head_ptr_holder.load(head_ptr) };
-        if head_ptr_guard.is_none() {
-            // store false in the pending field of the descriptor...as there is a possibility of
-            // helpers reaching here only after the update has been done...so they return early
-            // without changing the pending...assume one thread does the CAS on the head_ptr and
-            // the tail_ptr and then gets preempted.. all helpers will then spin perpetually or
-            // untill that thread gets rescheduled...so before returning we store false in the
-            // pending field to avoid such a scenario
-            unsafe {
-                (*actual_descriptor_guard.data)
-                    .pending
-                    .store(false, Ordering::SeqCst);
-            }
-            return;
-        }
-        //println!("hg");
+        // decided by the initiator before the descriptor was published, so every helper moves the
+        // tail to the same node
+        let prev = unsafe { (*actual_descriptor_guard.data).prev };
+        // protected before we look at the status like our node, the skipped count is written into
+        // it before the tail moves there
+        let mut prev_holder = HazPtrHolder::default();
+        let prev_guard =
+            (!prev.is_null()).then(|| unsafe { prev_holder.load(&AtomicPtr::new(prev)) });
         let pending = unsafe { &(*actual_descriptor_guard.data).pending };
         let status = unsafe { &(*actual_descriptor_guard.data).status };
+        let mut step = 0;
         loop {
-            match pending.load(Ordering::SeqCst) {
-                true => match status.load(Ordering::SeqCst) {
+            match pending.load(Ordering::Acquire) {
+                true => match status.load(Ordering::Acquire) {
                     2 => {
+                        if !prev.is_null() {
+                            self.skip(
+                                unsafe { &*actual_descriptor_guard.data },
+                                &actual_tail_ptr_guard,
+                                prev,
+                            );
+                        }
                         unsafe {
                             (*actual_descriptor_guard.data)
                                 .success
-                                .store(true, Ordering::SeqCst)
+                                .store(true, Ordering::Release);
+                            // before the tail moves on, so that a delete that finds the list
+                            // empty afterwards can not finish the announcement a second time. A
+                            // node that lost its value to a remove leaves the announcement to the
+                            // next delete
+                            if actual_tail_ptr_guard.state.load(Ordering::Acquire) != REMOVED {
+                                (*actual_descriptor_guard.data).finish_announced();
+                            }
                         }
                         tail_ptr.compare_exchange(
                             actual_tail_ptr_guard.data,
                             prev,
-                            Ordering::SeqCst,
-                            Ordering::SeqCst,
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
                         );
-                        //println!("Once every delete");
-                        pending.store(false, Ordering::SeqCst);
-                        if unsafe {
-                            (*actual_tail_ptr_guard)
+                        pending.store(false, Ordering::Release);
+                        // the nodes of a batch are left to the initiator, which still has to take
+                        // the values out of them
+                        if unsafe { (*actual_descriptor_guard.data).count } == 1
+                            && actual_tail_ptr_guard
                                 .retired
-                                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
                                 .is_ok()
-                        } {
-                            let mut hazholder = HazPtrHolder::default();
-                            let mut wrapper = unsafe {
-                                hazholder.swap(
-                                    &AtomicPtr::new(actual_tail_ptr_guard.data),
-                                    std::ptr::null_mut(),
-                                    &DELETER1,
-                                )
-                            };
-                            if let Some(mut wrapper) = wrapper {
-                                wrapper.retire();
-                            }
+                        {
+                            self.retire(actual_tail_ptr_guard.data);
                         }
                         return;
                     }
                     1 => {
-                        //println!("a");
-                        let taken_value =
-                            unsafe { std::ptr::read(&(*actual_tail_ptr_guard.data).value) };
-                        let mut init = MaybeUninit::uninit();
-                        unsafe { init.write(taken_value) };
-                        let init_ptr = Box::into_raw(Box::new(init));
-                        unsafe {
-                            (*actual_descriptor_guard.data)
-                                .taken_value
-                                .store(init_ptr, Ordering::SeqCst);
-                            (*actual_descriptor_guard.data)
-                                .init_stored
-                                .store(true, Ordering::SeqCst);
+                        // Every helper claims the values of the nodes that are still there, which
+                        // only the first one to get to a node wins and which changes nothing once
+                        // it did, so nobody waits for anybody else to finish. The values stay in
+                        // the nodes, the initiator moves them out once the delete is done.
+                        let descriptor = unsafe { &*actual_descriptor_guard.data };
+                        if !descriptor.announcement.is_null() {
+                            // except for an announced delete, whose owner may never have seen the
+                            // node. The helper that wins the claim moves the value into the
+                            // announcement and the others wait for it to finish the write, so an
+                            // announced delete does block at this one step
+                            match actual_tail_ptr_guard.claim(TAKEN) {
+                                Claim::Won => {
+                                    unsafe {
+                                        *(*descriptor.announcement).result.get() =
+                                            Some(actual_tail_ptr_guard.take());
+                                    }
+                                    descriptor.init_stored.store(true, Ordering::Release);
+                                }
+                                Claim::Removed => {
+                                    self.claimed(descriptor, &actual_tail_ptr_guard);
+                                    continue;
+                                }
+                                Claim::Taken | Claim::Busy => {}
+                            }
+                            if descriptor.init_stored.load(Ordering::Acquire) {
+                                self.claimed(descriptor, &actual_tail_ptr_guard);
+                            } else {
+                                sync::spin_loop();
+                                self.backoff.snooze(step);
+                                step = step.saturating_add(1);
+                            }
+                            continue;
+                        }
+                        // The rest of a batch only gets retired by the initiator after the status
+                        // moved on, so a node we protect while the status is still 1 stays
+                        // around for as long as we look at it, and nothing but a remove that is
+                        // asking its predicate touches it before then. A predicate is the only
+                        // thing a delete ever waits for.
+                        let mut node_holder = HazPtrHolder::default();
+                        let mut node = actual_tail_ptr_guard.data;
+                        let mut removed = 0;
+                        let mut walked = 0;
+                        while walked < descriptor.count {
+                            unsafe { node_holder.load(&AtomicPtr::new(node)) }
+                                .expect("Has to be there")
+                                .keep();
+                            if status.load(Ordering::Acquire) != 1 {
+                                break;
+                            }
+                            match unsafe { (*node).claim(TAKEN) } {
+                                Claim::Won | Claim::Taken => {}
+                                Claim::Removed => removed += 1,
+                                Claim::Busy => {
+                                    sync::spin_loop();
+                                    self.backoff.snooze(step);
+                                    step = step.saturating_add(1);
+                                    continue;
+                                }
+                            }
+                            walked += 1;
+                            node = unsafe { (*node).prev.load(Ordering::Acquire) };
+                        }
+                        if walked == descriptor.count {
+                            // every helper that gets here counted the same nodes
+                            descriptor.removed.store(removed, Ordering::Release);
+                            self.claimed(descriptor, &actual_tail_ptr_guard);
                         }
-                        status.compare_exchange(1, 2, Ordering::SeqCst, Ordering::SeqCst);
                         continue;
                     }
                     0 => {
-                        let current = tail_ptr.load(Ordering::SeqCst);
+                        let approval = unsafe { &(*actual_descriptor_guard.data).approval };
+                        match approval.load(Ordering::Acquire) {
+                            ASKING => {
+                                sync::spin_loop();
+                                self.backoff.snooze(step);
+                                step = step.saturating_add(1);
+                                continue;
+                            }
+                            DECLINED => {
+                                pending.store(false, Ordering::Release);
+                                break;
+                            }
+                            _ => {}
+                        }
+                        let current = tail_ptr.load(Ordering::Acquire);
                         // the idea is to make the swapping of the tail_ptr the last step
                         // therefore... helper threads will help when required and will just
                         // instantly return when helping is not required or when pointer that we
                         // expected to be stored into the tail_ptr is not actually there
-                        //println!("0");
                         if current != actual_tail_ptr_guard.data {
-                            pending.store(false, Ordering::SeqCst);
+                            pending.store(false, Ordering::Release);
                             break;
                         }
-                        if prev.is_null() {
-                            head_ptr.compare_exchange(
-                                actual_tail_ptr_guard.data,
-                                std::ptr::null_mut(),
-                                Ordering::SeqCst,
-                                Ordering::SeqCst,
-                            );
+                        // checked only after the tail, another descriptor for the same
+                        // announcement finishes it before it moves the tail away from its node.
+                        // It has to come before the head is emptied though, the delete is
+                        // committed from there on and a helper that gave up afterwards would
+                        // leave the tail on a node the head no longer leads to
+                        if unsafe { (*actual_descriptor_guard.data).announced_finished() } {
+                            pending.store(false, Ordering::Release);
+                            break;
+                        }
+                        // An unlink may have taken the node in front of ours out of the list since
+                        // the initiator looked, or one out of the middle of a batch. None can get
+                        // in while we hold the tail descriptor.
+                        let descriptor = unsafe { &*actual_descriptor_guard.data };
+                        let moved = if descriptor.count > 1 {
+                            self.unlinks.load(Ordering::SeqCst) != descriptor.unlinks
+                        } else {
+                            !prev.is_null()
+                                && actual_tail_ptr_guard.prev.load(Ordering::SeqCst) != prev
+                        };
+                        if moved {
+                            pending.store(false, Ordering::Release);
+                            break;
+                        }
+                        // we hold the head descriptor so no insert can link a node in front of the
+                        // last one from here on, but one that finished just before we got it may
+                        // have done so after the initiator looked at the prev field
+                        if prev.is_null()
+                            && !unsafe { actual_tail_ptr_guard.prev.load(Ordering::Acquire) }
+                                .is_null()
+                        {
+                            pending.store(false, Ordering::Release);
+                            break;
                         }
-                        //println!("hgswap");
-                        status.compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst);
+                        status.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire);
                         continue;
                     }
                     _ => unreachable!(),
@@ -700,4 +1649,42 @@ impl<T> LinkedList<T> {
             }
         }
     }
+
+    // An announced delete that found the list empty. It sits in the head descriptor, so no
+    // insert can get past an empty list until it is done, and the decision whether the list is
+    // empty is taken exactly once through the status so that a helper that looks at the tail late
+    // can not finish the announcement while another descriptor is taking a value for it.
+    fn loop_empty(&self, current_descriptor: *mut Descriptor<T>) {
+        let descriptor = unsafe { &*current_descriptor };
+        loop {
+            match descriptor.pending.load(Ordering::Acquire) {
+                true => match descriptor.status.load(Ordering::Acquire) {
+                    0 => {
+                        let decision = if self.tail.load(Ordering::Acquire).is_null() {
+                            1
+                        } else {
+                            2
+                        };
+                        descriptor.status.compare_exchange(
+                            0,
+                            decision,
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                        );
+                    }
+                    1 => {
+                        descriptor.success.store(true, Ordering::Release);
+                        descriptor.finish_announced();
+                        descriptor.pending.store(false, Ordering::Release);
+                        return;
+                    }
+                    _ => {
+                        descriptor.pending.store(false, Ordering::Release);
+                        return;
+                    }
+                },
+                false => return,
+            }
+        }
+    }
 }
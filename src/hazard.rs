@@ -1,19 +1,26 @@
 #![allow(unexpected_cfgs)]
-use crate::sync::atomic::{AtomicBool, AtomicPtr};
-use std::collections::HashSet;
-use std::convert::AsRef;
-use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut};
-use std::ptr::NonNull;
-use std::sync::atomic::Ordering;
+use crate::backoff::{Backoff, Exponential};
+use crate::pool;
+use crate::sync::CachePadded;
+use crate::sync::atomic::{AtomicBool, AtomicPtr, fence};
+use alloc::boxed::Box;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeSet as ProtectedSet;
+use core::convert::AsRef;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use core::sync::atomic::Ordering;
+#[cfg(feature = "std")]
+use std::collections::HashSet as ProtectedSet;
 
 #[cfg(not(loom))]
 pub(crate) static SHARED_DOMAIN: HazPtrDomain = HazPtrDomain {
     list: HazPtrs {
-        head: AtomicPtr::new(std::ptr::null_mut()),
+        head: AtomicPtr::new(core::ptr::null_mut()),
     },
     ret: Retired {
-        head: AtomicPtr::new(std::ptr::null_mut()),
+        head: AtomicPtr::new(core::ptr::null_mut()),
     },
 };
 
@@ -21,10 +28,10 @@ pub(crate) static SHARED_DOMAIN: HazPtrDomain = HazPtrDomain {
 loom::lazy_static! {
     static ref SHARED_DOMAIN: HazPtrDomain = HazPtrDomain {
         list: HazPtrs {
-            head: AtomicPtr::new(std::ptr::null_mut()),
+            head: AtomicPtr::new(core::ptr::null_mut()),
         },
         ret: Retired {
-            head: AtomicPtr::new(std::ptr::null_mut()),
+            head: AtomicPtr::new(core::ptr::null_mut()),
         },
     };
 }
@@ -40,7 +47,7 @@ pub struct Guard<'a, T> {
 
 impl<T> AsRef<T> for Guard<'_, T> {
     fn as_ref(&self) -> &T {
-        &(*self)
+        self
     }
 }
 
@@ -62,17 +69,39 @@ impl<T> DerefMut for Guard<'_, T> {
     }
 }
 
+impl<T> Guard<'_, T> {
+    // Ends the borrow of the holder but leaves the pointer protected until the holder loads
+    // another one or goes away, for a caller that has to keep a pointer protected across calls.
+    pub(crate) fn keep(self) -> *mut T {
+        let data = self.data;
+        core::mem::forget(self);
+        data
+    }
+}
+
 impl<T> Drop for Guard<'_, T> {
     fn drop(&mut self) {
         self.hazptr
             .ptr
-            .store(std::ptr::null_mut(), Ordering::SeqCst);
-        self.hazptr.flag.store(true, Ordering::SeqCst);
+            .store(core::ptr::null_mut(), Ordering::Release);
+    }
+}
+
+// The hazard pointer goes back to the domain only once the holder is gone. Releasing it from the
+// guard instead would let another thread acquire it while this holder still thinks it owns it,
+// and a load that finds a null pointer never creates a guard at all, which used to leak the
+// record for good.
+impl Drop for HazPtrHolder {
+    fn drop(&mut self) {
+        if let Some(hazptr) = self.0 {
+            hazptr.ptr.store(core::ptr::null_mut(), Ordering::Release);
+            hazptr.flag.store(true, Ordering::Release);
+        }
     }
 }
 
 impl HazPtrHolder {
-    /// SAFETY:
+    /// # Safety
     ///   1. The user must pass a valid pointer. Passing in invalid pointers such as a misaligned
     ///      one will cause undefined behaviour.
     ///   2. If a null pointer is passed that will be taken care of by the implementation as we
@@ -85,16 +114,16 @@ impl HazPtrHolder {
             self.0 = Some(ptr);
             ptr
         };
-        let mut ptr1 = ptr.load(Ordering::SeqCst);
-        let ret = loop {
+        let mut ptr1 = ptr.load(Ordering::Acquire);
+        loop {
             hazptr.protect(ptr1 as *mut ());
-            let ptr2 = ptr.load(Ordering::SeqCst);
+            let ptr2 = ptr.load(Ordering::Acquire);
             if ptr1 == ptr2 {
-                if let Some(_) = NonNull::new(ptr1) {
+                if NonNull::new(ptr1).is_some() {
                     let data = ptr1;
                     break Some(Guard {
-                        hazptr: &hazptr,
-                        data: data,
+                        hazptr,
+                        data,
                         _marker: PhantomData,
                     });
                 } else {
@@ -103,11 +132,10 @@ impl HazPtrHolder {
             } else {
                 ptr1 = ptr2;
             }
-        };
-        return ret;
+        }
     }
 
-    ///SAFETY:
+    /// # Safety
     ///  1. Swap ensures that the old pointer gets retired. The user must make sure that similar to
     ///     the load method, a valid pointer is passed failing which will cause undefined
     ///     behaviour.
@@ -120,21 +148,21 @@ impl HazPtrHolder {
         ptr: *mut T,
         deleter: &'static dyn Deleter,
     ) -> Option<HazPtrObjectWrapper<'_, T>> {
-        let current = atomic.load(Ordering::SeqCst);
-        atomic.store(ptr, Ordering::SeqCst);
+        let current = atomic.load(Ordering::Acquire);
+        atomic.store(ptr, Ordering::Release);
         if current.is_null() {
-            return None;
+            None
         } else {
             let wrapper = HazPtrObjectWrapper {
                 inner: current,
                 domain: &SHARED_DOMAIN,
-                deleter: deleter,
+                deleter,
             };
-            return Some(wrapper);
+            Some(wrapper)
         }
     }
 
-    ///SAFETY:
+    /// # Safety
     ///  1. This method provides a way to get the wrapper to call the retire method if the user is
     ///     not relying on swap. It must be used with care as repeatedly using load without
     ///     using this method and calling retire on it will lead to memory leaks.
@@ -143,17 +171,17 @@ impl HazPtrHolder {
         atomic: &'_ AtomicPtr<T>,
         deleter: &'static dyn Deleter,
     ) -> Option<HazPtrObjectWrapper<'_, T>> {
-        let current = atomic.load(Ordering::SeqCst);
-        atomic.store(std::ptr::null_mut(), Ordering::SeqCst);
+        let current = atomic.load(Ordering::Acquire);
+        atomic.store(core::ptr::null_mut(), Ordering::Release);
         if current.is_null() {
-            return None;
+            None
         } else {
             let wrapper = HazPtrObjectWrapper {
                 inner: current,
                 domain: &SHARED_DOMAIN,
-                deleter: deleter,
+                deleter,
             };
-            return Some(wrapper);
+            Some(wrapper)
         }
     }
 
@@ -169,20 +197,28 @@ impl HazPtrHolder {
     }
 }
 
+// The protected pointer is written by its owner on every load and read by every reclaiming
+// thread, so it is padded to keep two records from sharing a cache line.
 pub(crate) struct HazPtr {
-    ptr: AtomicPtr<()>,
+    ptr: CachePadded<AtomicPtr<()>>,
     next: AtomicPtr<HazPtr>,
     flag: AtomicBool,
 }
 
 impl HazPtr {
+    // Publishing a hazard and then checking that the pointer is still linked is a store followed
+    // by a load of a different location, the one pattern acquire and release can not order. The
+    // fence pairs with the one in reclaim: either the reclaiming thread sees our hazard or we see
+    // that the pointer got unlinked and try again. Every other access in the crate can do without
+    // sequential consistency because of these two.
     pub fn protect(&self, ptr: *mut ()) {
-        self.ptr.store(ptr, Ordering::SeqCst);
+        self.ptr.store(ptr, Ordering::Release);
+        fence(Ordering::SeqCst);
     }
 }
 
 pub trait HazPtrObject {
-    fn domain<'a>(&'a self) -> &'a HazPtrDomain;
+    fn domain(&self) -> &HazPtrDomain;
     fn retire(&mut self);
 }
 
@@ -205,65 +241,46 @@ impl<T> DerefMut for HazPtrObjectWrapper<'_, T> {
     }
 }
 
-impl<T> HazPtrObject for HazPtrObjectWrapper<'_, T> {
-    fn domain<'a>(&'a self) -> &'a HazPtrDomain {
+// Retired objects sit on the retired list of the whole process and may be dropped by another
+// thread long after whoever retired them is gone, so they must not borrow anything.
+impl<T: 'static> HazPtrObject for HazPtrObjectWrapper<'_, T> {
+    fn domain(&self) -> &HazPtrDomain {
         self.domain
     }
 
     ///SAFETY:
     ///  The user must make sure that a retired pointer is not retired again.
     fn retire(&mut self) {
+        let domain = self.domain;
+        if !self.inner.is_null() {
+            self.defer_retire();
+        }
+        unsafe {
+            domain.ret.reclaim(&domain.list);
+        }
+    }
+}
+
+impl<T: 'static> HazPtrObjectWrapper<'_, T> {
+    // Pushes the pointer onto the retired list without attempting a reclamation pass. This is
+    // used when a caller wants to retire a whole batch of pointers first and reclaim once at the
+    // end, so that a panicking destructor halfway through cannot leave the rest of the batch
+    // unretired.
+    pub(crate) fn defer_retire(&mut self) {
         if self.inner.is_null() {
-            let domain = self.domain();
-            unsafe {
-                (&domain.ret).reclaim(&domain.list);
-            }
             return;
         }
-        let domain = self.domain();
-        let current = (&domain.ret.head).load(Ordering::SeqCst);
-        loop {
-            let ret = Ret {
-                ptr: self.inner as *mut dyn Uniform,
-                next: AtomicPtr::new(std::ptr::null_mut()),
-                deleter: self.deleter,
-            };
-            if current.is_null() {
-                let boxed = Box::leak(Box::new(ret));
-                if domain
-                    .ret
-                    .head
-                    .compare_exchange(
-                        std::ptr::null_mut(),
-                        boxed,
-                        Ordering::SeqCst,
-                        Ordering::SeqCst,
-                    )
-                    .is_err()
-                {
-                    let drop = unsafe { Box::from_raw(boxed) };
-                    std::mem::drop(drop);
-                } else {
-                    unsafe { (&domain.ret).reclaim(&domain.list) };
-                    break;
-                }
-            } else {
-                ret.next.store(current, Ordering::SeqCst);
-                let boxed = Box::leak(Box::new(ret));
-                if domain
-                    .ret
-                    .head
-                    .compare_exchange(current, boxed, Ordering::SeqCst, Ordering::SeqCst)
-                    .is_err()
-                {
-                    let drop = unsafe { Box::from_raw(boxed) };
-                    std::mem::drop(drop);
-                } else {
-                    unsafe { (&domain.ret).reclaim(&domain.list) };
-                    break;
-                }
-            }
-        }
+        let ret = Ret {
+            ptr: self.inner as *mut dyn Uniform,
+            next: AtomicPtr::new(core::ptr::null_mut()),
+            deleter: self.deleter,
+        };
+        let ret = if self.deleter.pooled() {
+            pool::alloc(ret)
+        } else {
+            Box::into_raw(Box::new(ret))
+        };
+        self.domain.ret.push(ret);
     }
 }
 
@@ -274,10 +291,10 @@ pub struct HazPtrDomain {
 
 impl HazPtrDomain {
     fn acquire(&self) -> &'static HazPtr {
-        if self.list.head.load(Ordering::SeqCst).is_null() {
+        if self.list.head.load(Ordering::Acquire).is_null() {
             let hazptr = HazPtr {
-                ptr: AtomicPtr::new(std::ptr::null_mut()),
-                next: AtomicPtr::new(std::ptr::null_mut()),
+                ptr: CachePadded::new(AtomicPtr::new(core::ptr::null_mut())),
+                next: AtomicPtr::new(core::ptr::null_mut()),
                 flag: AtomicBool::new(false),
             };
             let raw = Box::into_raw(Box::new(hazptr));
@@ -285,35 +302,38 @@ impl HazPtrDomain {
                 .list
                 .head
                 .compare_exchange(
-                    std::ptr::null_mut(),
+                    core::ptr::null_mut(),
                     raw,
-                    Ordering::SeqCst,
-                    Ordering::SeqCst,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
                 )
                 .is_ok()
             {
                 return unsafe { &*raw };
             } else {
                 let drop = unsafe { Box::from_raw(raw) };
-                std::mem::drop(drop);
+                core::mem::drop(drop);
             }
         }
-        let mut current = (&self.list.head).load(Ordering::SeqCst);
+        let mut current = self.list.head.load(Ordering::Acquire);
         while !current.is_null() {
             if unsafe { &(*current).flag }
-                .compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst)
+                .compare_exchange(true, false, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
             {
                 return unsafe { &(*current) };
             } else {
-                current = unsafe { (&(*current).next).load(Ordering::SeqCst) };
+                current = unsafe { (*current).next.load(Ordering::Acquire) };
             }
         }
-        let mut now = self.list.head.load(Ordering::SeqCst);
+        // the domain is shared by every list so there is no per list strategy to pick from here,
+        // pushing a new record only fails when other threads are doing the same though
+        let mut now = self.list.head.load(Ordering::Acquire);
+        let mut step = 0;
         loop {
             let mut new = HazPtr {
-                ptr: AtomicPtr::new(std::ptr::null_mut()),
-                next: AtomicPtr::new(std::ptr::null_mut()),
+                ptr: CachePadded::new(AtomicPtr::new(core::ptr::null_mut())),
+                next: AtomicPtr::new(core::ptr::null_mut()),
                 flag: AtomicBool::new(false),
             };
             new.next = AtomicPtr::new(now);
@@ -321,23 +341,25 @@ impl HazPtrDomain {
             if self
                 .list
                 .head
-                .compare_exchange(now, boxed, Ordering::SeqCst, Ordering::SeqCst)
+                .compare_exchange(now, boxed, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
             {
                 return unsafe { &*boxed };
             } else {
-                now = self.list.head.load(Ordering::SeqCst);
+                now = self.list.head.load(Ordering::Acquire);
                 let drop = unsafe { Box::from_raw(boxed) };
-                std::mem::drop(drop);
+                core::mem::drop(drop);
+                Exponential::new().snooze(step);
+                step = step.saturating_add(1);
                 while !current.is_null() {
                     let flag = unsafe { &(*current).flag };
                     if flag
-                        .compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst)
+                        .compare_exchange(true, false, Ordering::AcqRel, Ordering::Acquire)
                         .is_ok()
                     {
                         return unsafe { &(*current) };
                     } else {
-                        current = unsafe { (&(*current).next).load(Ordering::SeqCst) };
+                        current = unsafe { (*current).next.load(Ordering::Acquire) };
                     }
                 }
             }
@@ -345,6 +367,34 @@ impl HazPtrDomain {
     }
 }
 
+// Under loom the domain is created anew for every execution of a model and dropped at its end,
+// when no thread is left that could hold a hazard. Whatever is still retired is reclaimed and the
+// records are freed then, otherwise they pile up over the executions until a model runs out of
+// memory.
+#[cfg(loom)]
+impl Drop for HazPtrDomain {
+    fn drop(&mut self) {
+        let mut ret = self.ret.head.swap(core::ptr::null_mut(), Ordering::AcqRel);
+        while !ret.is_null() {
+            let next = unsafe { (*ret).next.load(Ordering::Acquire) };
+            let (ptr, deleter) = unsafe { ((*ret).ptr, (*ret).deleter) };
+            if deleter.pooled() {
+                unsafe { pool::recycle(ret) };
+            } else {
+                let _ = unsafe { Box::from_raw(ret) };
+            }
+            deleter.delete(ptr);
+            ret = next;
+        }
+        let mut hazptr = self.list.head.swap(core::ptr::null_mut(), Ordering::AcqRel);
+        while !hazptr.is_null() {
+            let next = unsafe { (*hazptr).next.load(Ordering::Acquire) };
+            let _ = unsafe { Box::from_raw(hazptr) };
+            hazptr = next;
+        }
+    }
+}
+
 pub(crate) struct HazPtrs {
     head: AtomicPtr<HazPtr>,
 }
@@ -365,19 +415,32 @@ pub(crate) struct Ret {
 
 pub trait Deleter {
     fn delete(&self, ptr: *mut dyn Uniform);
+
+    // Whether the deleter hands its blocks to the recycling pool. The record that keeps a retired
+    // pointer on the retired list then comes from the pool as well, so that retiring never calls
+    // the allocator for a pooled list and never touches the pool for any other.
+    fn pooled(&self) -> bool {
+        false
+    }
 }
 
 /// SAFETY:
 ///   1. The user would have to pass an instance of one of the two zero sized types defined below:
-///     DropBox and DropPointer on the basis of how the actual raw pointer to the underlying type
-///     was created. This is necessary because using the drop_in_place() method on every pointer will
-///     not dealloate the instance of the box for all those pointers created using Box::into_raw().
+///      DropBox and DropPointer on the basis of how the actual raw pointer to the underlying type
+///      was created. This is necessary because using the drop_in_place() method on every pointer will
+///      not dealloate the instance of the box for all those pointers created using Box::into_raw().
 ///   2. The user must create the instance using static as the trait object must have a static
 ///      lifetime because we never know when the delete method on that deleter will be called.
 ///      Using static does not come with any memory overhead as the underlying type would be a zero
 ///      sized type.
 pub struct DropBox;
 
+impl Default for DropBox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DropBox {
     pub const fn new() -> Self {
         DropBox
@@ -385,16 +448,23 @@ impl DropBox {
 }
 
 impl Deleter for DropBox {
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     fn delete(&self, ptr: *mut dyn Uniform) {
-        if let Some(_) = NonNull::new(ptr) {
+        if NonNull::new(ptr).is_some() {
             let drop = unsafe { Box::from_raw(ptr) };
-            std::mem::drop(drop);
+            core::mem::drop(drop);
         }
     }
 }
 
 pub struct DropPointer;
 
+impl Default for DropPointer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DropPointer {
     pub const fn new() -> Self {
         DropPointer
@@ -402,87 +472,134 @@ impl DropPointer {
 }
 
 impl Deleter for DropPointer {
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     fn delete(&self, ptr: *mut dyn Uniform) {
-        if let Some(_) = NonNull::new(ptr) {
+        if NonNull::new(ptr).is_some() {
             unsafe {
-                std::ptr::drop_in_place(ptr);
+                core::ptr::drop_in_place(ptr);
             }
         }
     }
 }
 
 impl Retired {
+    fn push(&self, ret: *mut Ret) {
+        let mut current = self.head.load(Ordering::Acquire);
+        loop {
+            unsafe { (*ret).next.store(current, Ordering::Release) };
+            match self
+                .head
+                .compare_exchange(current, ret, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return,
+                Err(now) => current = now,
+            }
+        }
+    }
+
     /// SAFETY:
     ///    The user must make sure that the reclaim method is not called on the list of retired
     ///    pointers contaning two similar pointers as this will lead to the same pointers being
     ///    dereferenced leading to undefined behaviour.
-    unsafe fn reclaim<'a>(&self, domain: &'a HazPtrs) {
-        let mut set = HashSet::new();
-        let mut current = (&(domain.head)).load(Ordering::SeqCst);
+    unsafe fn reclaim(&self, domain: &HazPtrs) {
+        // The guard owns everything we have taken off the retired list but not yet freed. If a
+        // deleter panics, dropping the guard during unwinding puts both the protected pointers and
+        // the ones we had not reached yet back onto the retired list.
+        let mut guard = Reinsert {
+            retired: self,
+            remaining: core::ptr::null_mut(),
+            pending: (self.head).swap(core::ptr::null_mut(), Ordering::AcqRel),
+        };
+        if guard.pending.is_null() {
+            return;
+        }
+        // The hazard pointers have to be scanned only after the retired list was taken. Everything
+        // we took was unlinked before the swap, so any reader that still validated one of those
+        // pointers must have published its hazard before the swap as well and the scan below will
+        // see it. Scanning first would miss a reader that protects a pointer between the scan and
+        // the moment it gets retired.
+        // see HazPtr::protect for the other half of this fence
+        fence(Ordering::SeqCst);
+        let mut set = ProtectedSet::new();
+        let mut current = (domain.head).load(Ordering::Acquire);
         while !current.is_null() {
-            let a = unsafe { (*current).ptr.load(Ordering::SeqCst) };
+            let a = unsafe { (*current).ptr.load(Ordering::Acquire) };
             set.insert(a);
-            current = unsafe { (&(*current).next).load(Ordering::SeqCst) };
+            current = unsafe { (*current).next.load(Ordering::Acquire) };
         }
-        let mut remaining = std::ptr::null_mut();
-        let mut now = (self.head).swap(std::ptr::null_mut(), Ordering::SeqCst);
-        while !now.is_null() {
+        while !guard.pending.is_null() {
+            let now = guard.pending;
+            guard.pending = unsafe { ((*now).next).load(Ordering::Acquire) };
             let check = unsafe { (*now).ptr };
             if !set.contains(&(check as *mut ())) {
+                // The node is unlinked before the deleter runs so that a panicking destructor
+                // never sees its pointer put back on the list and dropped a second time.
                 let deleter = unsafe { (*now).deleter };
-                deleter.delete(check);
-                let go = now;
-                now = unsafe { ((*now).next).load(Ordering::SeqCst) };
-                let drop = unsafe { Box::from_raw(go) };
-                std::mem::drop(drop);
-            } else {
-                let next = unsafe { ((*now).next).load(Ordering::SeqCst) };
-                unsafe { (*now).next.store(remaining, Ordering::SeqCst) };
-                if remaining.is_null() {
-                    remaining = now;
-                    unsafe {
-                        (*remaining)
-                            .next
-                            .store(std::ptr::null_mut(), Ordering::SeqCst);
-                    }
+                if deleter.pooled() {
+                    unsafe { pool::recycle(now) };
                 } else {
-                    unsafe {
-                        (*now).next.store(remaining, Ordering::SeqCst);
-                    }
-                    remaining = now;
+                    let _ = unsafe { Box::from_raw(now) };
                 }
-                now = next;
+                deleter.delete(check);
+            } else {
+                unsafe { (*now).next.store(guard.remaining, Ordering::Release) };
+                guard.remaining = now;
             }
         }
-        // The following code guarantees that no elements are ever lost
+    }
+
+    // The following code guarantees that no elements are ever lost
+    fn reinsert(&self, mut remaining: *mut Ret) {
         loop {
             if self
                 .head
                 .compare_exchange(
-                    std::ptr::null_mut(),
+                    core::ptr::null_mut(),
                     remaining,
-                    Ordering::SeqCst,
-                    Ordering::Relaxed,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
                 )
                 .is_ok()
             {
                 return;
+            } else if remaining.is_null() {
+                remaining = self.head.swap(core::ptr::null_mut(), Ordering::AcqRel);
             } else {
-                if remaining.is_null() {
-                    remaining = self.head.swap(std::ptr::null_mut(), Ordering::SeqCst);
-                } else {
-                    let mut safety_variable = remaining;
-                    while unsafe { !(*safety_variable).next.load(Ordering::SeqCst).is_null() } {
-                        safety_variable = unsafe { (*safety_variable).next.load(Ordering::SeqCst) };
-                    }
-                    let to_be_swapped = self.head.swap(std::ptr::null_mut(), Ordering::SeqCst);
-                    unsafe {
-                        (*safety_variable)
-                            .next
-                            .store(to_be_swapped, Ordering::SeqCst);
-                    }
+                let last = Self::last(remaining);
+                let to_be_swapped = self.head.swap(core::ptr::null_mut(), Ordering::AcqRel);
+                unsafe {
+                    (*last).next.store(to_be_swapped, Ordering::Release);
                 }
             }
         }
     }
+
+    fn last(mut ret: *mut Ret) -> *mut Ret {
+        while unsafe { !(*ret).next.load(Ordering::Acquire).is_null() } {
+            ret = unsafe { (*ret).next.load(Ordering::Acquire) };
+        }
+        ret
+    }
+}
+
+struct Reinsert<'a> {
+    retired: &'a Retired,
+    remaining: *mut Ret,
+    pending: *mut Ret,
+}
+
+impl Drop for Reinsert<'_> {
+    fn drop(&mut self) {
+        let mut list = self.remaining;
+        if list.is_null() {
+            if self.pending.is_null() {
+                return;
+            }
+            list = self.pending;
+        } else {
+            let last = Retired::last(list);
+            unsafe { (*last).next.store(self.pending, Ordering::Release) };
+        }
+        self.retired.reinsert(list);
+    }
 }
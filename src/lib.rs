@@ -1,10 +1,30 @@
+#![cfg_attr(not(any(feature = "std", loom)), no_std)]
+#![allow(unexpected_cfgs)]
+
+extern crate alloc;
+
+pub(crate) mod announce;
+pub mod backoff;
+pub mod broadcast;
+pub mod channel;
+pub mod combining;
 pub mod descriptor;
 pub mod hazard;
 pub mod list;
+pub mod pool;
 pub mod sync;
+#[cfg(feature = "std")]
+pub(crate) mod waiters;
 
+pub use crate::backoff::{Backoff, Exponential, NoBackoff, Spin};
+pub use crate::broadcast::{Broadcast, Subscriber};
+pub use crate::channel::{Receiver, Sender, channel};
+pub use crate::combining::CombiningList;
 use crate::descriptor::Descriptor;
 use crate::hazard::{Deleter, HazPtrObject};
 pub use crate::hazard::{DropBox, DropPointer, HazPtrHolder};
-pub use crate::list::LinkedList;
 use crate::list::Node;
+pub use crate::list::{Closed, DeleteIfError, Drained, EntryHandle, LinkedList};
+pub use crate::pool::Recycle;
+#[cfg(feature = "std")]
+pub use crate::waiters::PopFuture;
@@ -1,97 +1,935 @@
 #![allow(dead_code)]
 use crate::Descriptor;
-use crate::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
-use std::marker::PhantomData;
-use std::sync::atomic::Ordering;
+use crate::HazPtrHolder;
+use crate::announce::Announcements;
+use crate::backoff::{Backoff, NoBackoff};
+use crate::descriptor::Inserted;
+use crate::hazard::Deleter;
+use crate::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, fence};
+use crate::sync::{self, CachePadded};
+#[cfg(feature = "std")]
+use crate::waiters::Waiters;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::sync::atomic::Ordering;
+
+static NO_BACKOFF: NoBackoff = NoBackoff::new();
 
 pub(crate) struct Node<T> {
-    pub(crate) value: T,
+    pub(crate) value: ManuallyDrop<T>,
     pub(crate) prev: AtomicPtr<Node<T>>,
     pub(crate) retired: AtomicBool,
     pub(crate) value_moved: AtomicBool,
+    // who gets the value, see claim below
+    pub(crate) state: AtomicUsize,
+    // one more than the index of the node it was linked in front of, so that the number of nodes
+    // between the tail and the head can be read off their two indices. A node linked into an
+    // empty list starts the count over and keeps the zero it was created with
+    pub(crate) index: AtomicUsize,
+    // how many nodes that lost their value to a remove were unlinked before this one became the
+    // tail, counted over the whole life of the list. Written before the tail moves here and never
+    // changed afterwards, so whoever reads the tail reads the matching count with it
+    pub(crate) skipped: AtomicUsize,
+    // set once the node was taken out of the middle of the list, before the node behind it stops
+    // pointing at it
+    pub(crate) unlinked: AtomicBool,
 }
 
 impl<T> Node<T> {
-    fn new(value: T) -> Self {
+    pub(crate) fn new(value: T) -> Self {
         Self {
-            value,
-            prev: AtomicPtr::new(std::ptr::null_mut()),
+            value: ManuallyDrop::new(value),
+            prev: AtomicPtr::new(core::ptr::null_mut()),
             // this field is to prevent that retirement of the same node more than once
             retired: AtomicBool::new(false),
             value_moved: AtomicBool::new(false),
+            state: AtomicUsize::new(FREE),
+            index: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            unlinked: AtomicBool::new(false),
+        }
+    }
+
+    // Whoever moves the state away from FREE gets the value. A delete takes it for good, a remove
+    // or a conditional delete only holds it while it asks its predicate and hands it back unless
+    // the value goes, so losing to one of those only means waiting until it made up its mind.
+    pub(crate) fn claim(&self, to: usize) -> Claim {
+        match self
+            .state
+            .compare_exchange(FREE, to, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => Claim::Won,
+            Err(TAKEN) => Claim::Taken,
+            Err(REMOVED) => Claim::Removed,
+            Err(_) => Claim::Busy,
+        }
+    }
+
+    // Moves the value out for whoever won the claim.
+    pub(crate) unsafe fn take(&self) -> T {
+        let value = unsafe { core::ptr::read(&*self.value) };
+        self.value_moved.store(true, Ordering::Release);
+        value
+    }
+
+    // Moves the value out if the predicate holds for it. Nobody can take the value away while the
+    // predicate looks at it, and a predicate that panics leaves it where it was.
+    fn remove_if(&self, predicate: &mut dyn FnMut(&T) -> bool, removed: &AtomicUsize) -> Option<T> {
+        if !matches!(self.claim(ASKING), Claim::Won) {
+            return None;
+        }
+        let unclaim = Unclaim(&self.state);
+        if !predicate(&self.value) {
+            return None;
+        }
+        core::mem::forget(unclaim);
+        Some(unsafe { self.remove(removed) })
+    }
+
+    // Moves the value out for whoever won the claim with ASKING and leaves the node REMOVED. It is
+    // counted as removed before the state says so, nothing counts a node as skipped while it is
+    // ASKING, so whoever does later always finds it counted as removed.
+    unsafe fn remove(&self, removed: &AtomicUsize) -> T {
+        removed.fetch_add(1, Ordering::SeqCst);
+        let value = unsafe { self.take() };
+        self.state.store(REMOVED, Ordering::Release);
+        value
+    }
+}
+
+// The states of a node. It is FREE while nobody has its value, ASKING while a predicate looks at
+// it, TAKEN once a delete moved it out and REMOVED once a remove or a cancel did while the node
+// was still linked. The node stays linked then until a walk unlinks it from the middle of the list
+// or it reaches the tail, where whatever takes it out finds nothing to hand out. A drain moves
+// every node it took out to TAKEN right away, the values stay in the nodes until the drained
+// iterator gets to them.
+pub(crate) const FREE: usize = 0;
+pub(crate) const ASKING: usize = 1;
+pub(crate) const TAKEN: usize = 2;
+pub(crate) const REMOVED: usize = 3;
+
+pub(crate) enum Claim {
+    Won,
+    Taken,
+    Removed,
+    // a predicate is looking at the value
+    Busy,
+}
+
+// Hands the value of a node back when dropped.
+pub(crate) struct Unclaim<'a>(pub(crate) &'a AtomicUsize);
+
+impl Drop for Unclaim<'_> {
+    fn drop(&mut self) {
+        self.0.store(FREE, Ordering::Release);
+    }
+}
+
+// The value is moved out of the node with a bitwise read on deletion, so the node only drops it
+// itself if that never happened.
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        if !self.value_moved.load(Ordering::Acquire) {
+            unsafe { ManuallyDrop::drop(&mut self.value) };
         }
     }
 }
 
-pub struct LinkedList<T> {
-    length: AtomicUsize,
-    pub(crate) head: AtomicPtr<Node<T>>,
-    pub(crate) tail: AtomicPtr<Node<T>>,
-    pub(crate) descriptor: AtomicPtr<Descriptor<T>>,
+// Producers CAS the head and its descriptor, consumers CAS the tail and its descriptor and
+// everybody bumps the length, so each of them gets a cache line of its own instead of bouncing one
+// shared line.
+pub struct LinkedList<T: 'static> {
+    pub(crate) length: CachePadded<AtomicUsize>,
+    pub(crate) head: CachePadded<AtomicPtr<Node<T>>>,
+    pub(crate) tail: CachePadded<AtomicPtr<Node<T>>>,
+    // inserts go through the head descriptor and deletes through the tail descriptor, so the two
+    // ends only ever wait for each other when the list is down to its last node
+    pub(crate) head_descriptor: CachePadded<AtomicPtr<Descriptor<T>>>,
+    pub(crate) tail_descriptor: CachePadded<AtomicPtr<Descriptor<T>>>,
+    // whether nodes and descriptors are taken from and handed back to the recycling pool instead
+    // of going through the allocator every time
+    pub(crate) pooled: bool,
+    // what the retry loops do before going around again
+    pub(crate) backoff: &'static dyn Backoff,
+    // only there in wait-free mode
+    pub(crate) announcements: Option<Box<Announcements<T>>>,
+    // the most values the list may hold, usize::MAX unless it was created with a limit
+    pub(crate) capacity: usize,
+    // set once by close, from within the head descriptor
+    pub(crate) closed: AtomicBool,
+    // how many nodes lost their value to a remove or a cancel, only ever grows. Less the skipped
+    // count of the tail that is how many of them still sit between the tail and the head, linked
+    // or as a gap in the indices, and count neither towards the length nor towards the capacity
+    pub(crate) removed: AtomicUsize,
+    // the skipped count as of the last time the list ran empty, for the first node linked into it
+    pub(crate) skipped: AtomicUsize,
+    // bumped by every unlink from the middle of the list, so that a batch can tell whether the
+    // chain it walked is still the same
+    pub(crate) unlinks: AtomicUsize,
+    // the threads parked in pop_blocking and pop_timeout
+    #[cfg(feature = "std")]
+    pub(crate) waiters: Waiters,
     marker: PhantomData<Node<T>>,
 }
 
-unsafe impl<T> Send for LinkedList<T> where T: Send {}
-unsafe impl<T> Sync for LinkedList<T> where T: Sync {}
+/// Returned by the deletes of a list that was closed and has given out every value it held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+/// A value inserted with `LinkedList::insert_from_head_with_handle`. The handle keeps the node of
+/// the value protected, so it can always look at whether the value is still there, even once the
+/// node was unlinked. Every handle holds on to a hazard pointer of its own, which every
+/// reclamation has to look at, so handles are best not kept around in large numbers. Dropping the
+/// handle leaves the value in the list.
+pub struct EntryHandle<'a, T: 'static> {
+    list: &'a LinkedList<T>,
+    holder: HazPtrHolder,
+    node: *mut Node<T>,
+}
+
+unsafe impl<T: 'static> Send for EntryHandle<'_, T> where T: Send {}
 
-impl<T> LinkedList<T> {
+impl<T: 'static> EntryHandle<'_, T> {
+    /// Takes the value back out of the list unless a delete took it first. The value stops
+    /// counting towards the length and the capacity right away, and the node is unlinked the same
+    /// way `LinkedList::remove_first` unlinks one, by walking the list from the tail up to it, so
+    /// a cancel takes time in the number of values in front of it. A predicate that is looking at
+    /// the value at the time is waited for.
+    pub fn try_cancel(self) -> Option<T> {
+        let node = unsafe { &*self.node };
+        let mut step = 0;
+        loop {
+            match node.claim(ASKING) {
+                Claim::Won => {
+                    let value = unsafe { node.remove(&self.list.removed) };
+                    self.list.length.fetch_sub(1, Ordering::Relaxed);
+                    // the walk unlinks the node once it gets to it, unless the node is at one of
+                    // the ends or a delete took it out of the list already
+                    self.list.walk(&mut |other| !core::ptr::eq(other, node));
+                    HazPtrHolder::try_reclaim();
+                    return Some(value);
+                }
+                Claim::Taken | Claim::Removed => return None,
+                Claim::Busy => {
+                    sync::spin_loop();
+                    self.list.backoff.snooze(step);
+                    step = step.saturating_add(1);
+                }
+            }
+        }
+    }
+
+    /// Whether the value is still in the list, for the moment.
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            unsafe { (*self.node).state.load(Ordering::Acquire) },
+            FREE | ASKING
+        )
+    }
+}
+
+/// The values `LinkedList::take_all` took out of a list, oldest first. The nodes were detached
+/// from the list as a whole and each one is retired as soon as its value was handed out, since
+/// other threads may still be looking at it. Whatever is left when this is dropped goes with it.
+/// Nodes whose value went to a remove before the list was drained are passed over, so there may
+/// be fewer values than the list held nodes.
+pub struct Drained<T: 'static> {
+    node: *mut Node<T>,
+    remaining: usize,
+    deleter: &'static dyn Deleter,
+}
+
+unsafe impl<T: 'static> Send for Drained<T> where T: Send {}
+
+impl<T: 'static> Drained<T> {
+    // Moves on to the node in front of the next one and returns the next one.
+    fn advance(&mut self) -> *mut Node<T> {
+        let node = self.node;
+        self.node = unsafe { (*node).prev.load(Ordering::Acquire) };
+        self.remaining -= 1;
+        node
+    }
+
+    fn retire(&self, node: *mut Node<T>) {
+        let mut holder = HazPtrHolder::default();
+        let wrapper =
+            unsafe { holder.swap(&AtomicPtr::new(node), core::ptr::null_mut(), self.deleter) };
+        if let Some(mut wrapper) = wrapper {
+            wrapper.defer_retire();
+        }
+    }
+}
+
+impl<T: 'static> Iterator for Drained<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while self.remaining > 0 {
+            let node = self.advance();
+            // the drain claimed every node that still had its value for us
+            let value = (unsafe { (*node).state.load(Ordering::Acquire) } != REMOVED)
+                .then(|| unsafe { (*node).take() });
+            self.retire(node);
+            if value.is_some() {
+                return value;
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+impl<T: 'static> Drop for Drained<T> {
+    fn drop(&mut self) {
+        // every node is retired before reclaiming even once, like when the list itself is dropped
+        while self.remaining > 0 {
+            let node = self.advance();
+            self.retire(node);
+        }
+        HazPtrHolder::try_reclaim();
+    }
+}
+
+/// Returned by `LinkedList::delete_from_tail_if` when it took nothing out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteIfError {
+    /// The list is empty for now.
+    Empty,
+    /// The predicate did not hold for the oldest value, which is still in the list.
+    Rejected,
+    /// The list was closed and has given out every value it held.
+    Closed,
+}
+
+unsafe impl<T: 'static> Send for LinkedList<T> where T: Send {}
+unsafe impl<T: 'static> Sync for LinkedList<T> where T: Sync {}
+
+impl<T: 'static> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: 'static> LinkedList<T> {
     pub fn new() -> Self {
+        Self::build(false)
+    }
+
+    /// Creates a list whose retired nodes and descriptors are recycled through a process wide
+    /// pool once the hazard domain has made sure no thread protects them anymore, so that the
+    /// steady state of inserts and deletes needs next to no calls into the allocator.
+    pub fn with_pool() -> Self {
+        Self::build(true)
+    }
+
+    /// Creates a list that never holds more than `capacity` values. `try_insert_from_head` hands
+    /// the value back once the list is full and `insert_from_head` waits for a delete to make room,
+    /// so a slow consumer pushes back on its producers instead of letting the list grow without
+    /// bound. A list with a capacity of zero can never take a value, every insert hands it back.
+    pub fn with_capacity_limit(capacity: usize) -> Self {
+        let mut list = Self::build(false);
+        list.capacity = capacity;
+        list
+    }
+
+    fn build(pooled: bool) -> Self {
         Self {
-            length: AtomicUsize::new(0),
-            head: AtomicPtr::new(std::ptr::null_mut()),
-            tail: AtomicPtr::new(std::ptr::null_mut()),
-            descriptor: AtomicPtr::new(std::ptr::null_mut()),
+            length: CachePadded::new(AtomicUsize::new(0)),
+            head: CachePadded::new(AtomicPtr::new(core::ptr::null_mut())),
+            tail: CachePadded::new(AtomicPtr::new(core::ptr::null_mut())),
+            head_descriptor: CachePadded::new(AtomicPtr::new(core::ptr::null_mut())),
+            tail_descriptor: CachePadded::new(AtomicPtr::new(core::ptr::null_mut())),
+            pooled,
+            backoff: &NO_BACKOFF,
+            announcements: None,
+            capacity: usize::MAX,
+            closed: AtomicBool::new(false),
+            removed: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            unlinks: AtomicUsize::new(0),
+            #[cfg(feature = "std")]
+            waiters: Waiters::new(),
             marker: PhantomData,
         }
     }
 
-    pub fn insert_from_head<'a>(&self, value: T) {
-        let boxed = Box::into_raw(Box::new(Node::new(value)));
+    /// Sets the strategy the retry loops of this list fall back on whenever a CAS fails or they
+    /// run into another pending operation, in place of retrying right away. It is meant to be
+    /// chained onto one of the constructors and takes a static for the same reason the deleters
+    /// do, e.g. `LinkedList::with_pool().with_backoff(&EXPONENTIAL)` given
+    /// `static EXPONENTIAL: Exponential = Exponential::new();`.
+    pub fn with_backoff(mut self, backoff: &'static dyn Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Inserts the value, or hands it back if the list was closed or has a capacity of zero. If
+    /// the list was created with a capacity limit and is full, this keeps trying until a delete
+    /// makes room, going through the backoff of the list in between.
+    pub fn insert_from_head(&self, value: T) -> Result<(), T> {
+        let mut value = value;
+        let mut step = 0;
+        loop {
+            match self.try_insert_from_head(value) {
+                Ok(()) => return Ok(()),
+                // no delete is ever going to make room in a list that can not hold anything
+                Err(back) if self.is_closed() || self.capacity == 0 => return Err(back),
+                Err(back) => value = back,
+            }
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    /// Inserts the value unless the list is at its capacity limit or closed, in which case
+    /// nothing happens and the value is handed back. Whether the list is full is decided by the
+    /// insert descriptor at the same point at which it would otherwise link the node, so no number
+    /// of concurrent inserts can ever take the list past the limit.
+    pub fn try_insert_from_head(&self, value: T) -> Result<(), T> {
+        if self.is_closed() {
+            return Err(value);
+        }
+        self.link(value)?;
+        #[cfg(feature = "std")]
+        self.waiters.notify_one();
+        Ok(())
+    }
+
+    /// Inserts the value like `insert_from_head` and hands back a handle that can take it out
+    /// again for as long as no delete got to it.
+    pub fn insert_from_head_with_handle(&self, value: T) -> Result<EntryHandle<'_, T>, T> {
+        let mut value = value;
+        let mut step = 0;
+        loop {
+            match self.try_insert_with_handle(value) {
+                Ok(handle) => return Ok(handle),
+                // no delete is ever going to make room in a list that can not hold anything
+                Err(back) if self.is_closed() || self.capacity == 0 => return Err(back),
+                Err(back) => value = back,
+            }
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    // The node is protected before it is published, a delete could otherwise retire it before
+    // the handle gets to it.
+    fn try_insert_with_handle(&self, value: T) -> Result<EntryHandle<'_, T>, T> {
+        if self.is_closed() {
+            return Err(value);
+        }
+        let node = self.alloc(Node::new(value));
+        let mut holder = HazPtrHolder::default();
+        unsafe { holder.load(&AtomicPtr::new(node)) }
+            .expect("Has to be there")
+            .keep();
+        self.link_node(node)?;
+        #[cfg(feature = "std")]
+        self.waiters.notify_one();
+        Ok(EntryHandle {
+            list: self,
+            holder,
+            node,
+        })
+    }
+
+    /// Closes the list for good. Inserts fail from then on and hand their value back, deletes
+    /// keep handing out the values that are left and report `Closed` once there are none, and
+    /// every thread or task waiting for a value is woken to find out. An insert that runs at the
+    /// same time either gets its value in before the close or gets it back, never after.
+    pub fn close(&self) {
+        self.seal();
+        #[cfg(feature = "std")]
+        self.waiters.notify_all();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Inserts all the values as one operation. They are chained together privately and then
+    /// linked in front of the head with a single descriptor, so they show up in the list all at
+    /// once, next to each other and in the order the iterator gave them, with nothing from other
+    /// inserts in between. If the list was created with a capacity limit the batch waits until
+    /// there is room for all of it, a batch that could never fit is handed back right away, just
+    /// like the whole batch is if the list was closed. In wait-free mode the batch still goes in
+    /// as one, but it is not announced and so only lock-free: the helpers of an announcement can
+    /// not agree on the indices of a whole chain without writing into nodes that may already be
+    /// gone.
+    pub fn insert_batch_from_head<I>(&self, values: I) -> Result<(), Vec<T>>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let Some((first, last, count)) = self.chain(values) else {
+            return Ok(());
+        };
+        let mut step = 0;
+        while count <= self.capacity && !self.is_closed() {
+            if self.splice(first, last, count) {
+                self.notify(count);
+                return Ok(());
+            }
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+        Err(self.unchain(first))
+    }
+
+    /// Moves every value of `other` in front of the head of this list, in the order they were
+    /// inserted into `other`. `other` gives them up in one step through `take_all` and this list
+    /// takes them in one step like any batch, so neither list is ever seen with a part of them.
+    /// The two steps are not one though, a descriptor of one list can not reach the other one
+    /// since any thread may still be helping it after the call returned and the list is gone, and
+    /// for a moment the values are in neither list.
+    /// This list gets a single try and never waits for room. If it is closed or does not have
+    /// room for all of the values next to what it holds already, nothing is appended and they go
+    /// back into `other` in one step, in front of whatever was inserted into it in the meantime.
+    /// Only what `other` would not take back anymore because it got closed or filled up in between
+    /// ends up in the error, which is empty otherwise.
+    pub fn append(&self, other: &LinkedList<T>) -> Result<(), Vec<T>> {
+        if self.is_closed() {
+            return Err(Vec::new());
+        }
+        let Some((first, last, count)) = self.chain(other.take_all()) else {
+            return Ok(());
+        };
+        if self.splice(first, last, count) {
+            self.notify(count);
+            return Ok(());
+        }
+        let Some((first, last, count)) = other.chain(self.unchain(first)) else {
+            return Err(Vec::new());
+        };
+        // a single try as well, the values came out of other but it may have filled up since
+        if other.splice(first, last, count) {
+            other.notify(count);
+            return Err(Vec::new());
+        }
+        Err(other.unchain(first))
+    }
+
+    // Chains the values together through the prev pointers of fresh nodes, oldest first. Returns
+    // the oldest and the newest node and how many there are, or None if there were no values.
+    fn chain<I>(&self, values: I) -> Option<(*mut Node<T>, *mut Node<T>, usize)>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut first: *mut Node<T> = core::ptr::null_mut();
+        let mut last: *mut Node<T> = core::ptr::null_mut();
+        let mut count = 0;
+        for value in values {
+            let node = self.alloc(Node::new(value));
+            if last.is_null() {
+                first = node;
+            } else {
+                unsafe { (*last).prev.store(node, Ordering::Relaxed) };
+            }
+            last = node;
+            count += 1;
+        }
+        (count > 0).then_some((first, last, count))
+    }
+
+    // Takes the values back out of a chain that splice turned away.
+    fn unchain(&self, first: *mut Node<T>) -> Vec<T> {
+        let mut back = Vec::new();
+        let mut node = first;
+        while !node.is_null() {
+            let prev = unsafe { (*node).prev.load(Ordering::Relaxed) };
+            back.push(self.take_back(node));
+            node = prev;
+        }
+        HazPtrHolder::try_reclaim();
+        back
+    }
+
+    // Wakes up to count waiting consumers after count values went in at once.
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    fn notify(&self, count: usize) {
+        #[cfg(feature = "std")]
+        for _ in 0..count {
+            self.waiters.notify_one();
+        }
+    }
+
+    fn link(&self, value: T) -> Result<(), T> {
+        self.link_node(self.alloc(Node::new(value)))
+    }
+
+    fn link_node(&self, boxed: *mut Node<T>) -> Result<(), T> {
+        if let Some(announcements) = &self.announcements {
+            self.announced_insert(announcements, boxed)?;
+            self.length.fetch_add(1, Ordering::Relaxed);
+            HazPtrHolder::try_reclaim();
+            return Ok(());
+        }
+        if self.splice(boxed, boxed, 1) {
+            return Ok(());
+        }
+        let value = self.take_back(boxed);
+        HazPtrHolder::try_reclaim();
+        Err(value)
+    }
+
+    // Links the count nodes from first to last, chained together through their prev pointers, in
+    // front of the head. Returns false if the list was full or closed, the nodes are left to the
+    // caller then.
+    fn splice(&self, first: *mut Node<T>, last: *mut Node<T>, count: usize) -> bool {
+        let mut step = 0;
         loop {
-            let current = self.head.load(Ordering::SeqCst);
+            let current = self.head.load(Ordering::Acquire);
             if current.is_null() {
+                if count > self.capacity || self.is_closed() {
+                    return false;
+                }
+                // a chain linked into an empty list starts the count over from zero
+                Self::number(first, count, 0);
                 match self.head.compare_exchange(
-                    std::ptr::null_mut(),
-                    boxed,
-                    Ordering::SeqCst,
+                    core::ptr::null_mut(),
+                    last,
                     Ordering::SeqCst,
+                    Ordering::Acquire,
                 ) {
                     Ok(_) => {
-                        // We dont CAS the tail because we dont have a method to insert from the
-                        // tail side.Therefore any possibility of some other thread inserting a
-                        // tail after we swap the head but before we manage to store the tail does
-                        // not  exist.
-                        // Updating the tail is the only reason we have this loop in the first
-                        // place otherwise the insert method has got all the capability to handle
-                        // the case where head is an atomic pointer storing a null pointer.
-                        self.tail.store(boxed, Ordering::SeqCst);
-                        //println!("Reached insert");
-                        self.length.fetch_add(1, Ordering::SeqCst);
-                        break;
+                        // The close may have taken effect after we looked, and this path does not
+                        // go through the head descriptor. Nobody can delete the nodes before the
+                        // tail points at them, so they are taken back out unless an insert that
+                        // came before the close linked a node in front of them already. The fence
+                        // pairs with the one in loop_close: either we see the flag or the closing
+                        // thread and every delete after it see our nodes.
+                        fence(Ordering::SeqCst);
+                        if self.is_closed()
+                            && self
+                                .head
+                                .compare_exchange(
+                                    last,
+                                    core::ptr::null_mut(),
+                                    Ordering::AcqRel,
+                                    Ordering::Acquire,
+                                )
+                                .is_ok()
+                        {
+                            return false;
+                        }
+                        // Winning the head CAS makes this chain the whole list, so the tail can
+                        // only be null or the node the last delete took out. That delete, or the
+                        // drain that emptied the list, may still be about to CAS the tail away
+                        // from it. A swap is a read-modify-write, so it is ordered after such a
+                        // CAS and the stale one fails, which loom does not do for a plain store.
+                        self.start_over(first);
+                        self.tail.swap(first, Ordering::AcqRel);
+                        self.length.fetch_add(count, Ordering::Relaxed);
+                        return true;
                     }
                     Err(_) => {
+                        self.backoff.snooze(step);
+                        step = step.saturating_add(1);
                         continue;
                     }
                 }
-            } else {
-                self.insert(boxed);
-                //println!("Reached insert");
-                self.length.fetch_add(1, Ordering::SeqCst);
-                break;
+            }
+            match self.insert_chain(first, last, count, None) {
+                Inserted::Linked => {
+                    self.length.fetch_add(count, Ordering::Relaxed);
+                    HazPtrHolder::try_reclaim();
+                    return true;
+                }
+                Inserted::Rejected => return false,
+                Inserted::Empty => {}
             }
         }
     }
 
-    pub fn delete_from_tail<'a>(&self) -> Option<T> {
-        let ret = self.delete();
+    /// Takes the oldest value out of the list. None means the list is empty for now, `Closed`
+    /// that it was closed and will stay empty.
+    pub fn delete_from_tail(&self) -> Result<Option<T>, Closed> {
+        let ret = match &self.announcements {
+            Some(announcements) => self.announced_delete(announcements),
+            None => self.delete(None),
+        };
         if ret.is_some() {
-            //println!("Reached decrement subcount");
-            self.length.fetch_sub(1, Ordering::SeqCst);
+            self.length.fetch_sub(1, Ordering::Relaxed);
         }
-        return ret;
+        // reclaiming only after the bookkeeping is done means a panicking destructor in some
+        // retired object can never leave the length out of sync with the list
+        HazPtrHolder::try_reclaim();
+        // A head that is not null while the tail is belongs to an insert into the empty list that
+        // is still going on, its value may yet show up.
+        if ret.is_none() && self.is_closed() {
+            fence(Ordering::SeqCst);
+            if self.head.load(Ordering::SeqCst).is_null() {
+                return Err(Closed);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Takes the oldest value out of the list only if the predicate holds for it. The predicate
+    /// looks at the value while the delete holds the node in its descriptor, so the value is
+    /// still the oldest one when it is taken, and nothing is taken out only to be put back. Every
+    /// other delete and, if it is the only value, every insert waits for the predicate, so it
+    /// should be quick. On a wait-free list it is not announced, since helpers would have to run
+    /// a predicate that only the caller has, so it competes for the descriptor slots like a plain
+    /// delete and nobody helps it.
+    pub fn delete_from_tail_if<F>(&self, mut predicate: F) -> Result<T, DeleteIfError>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let ret = self.delete_if(&mut predicate);
+        if ret.is_ok() {
+            self.length.fetch_sub(1, Ordering::Relaxed);
+        }
+        HazPtrHolder::try_reclaim();
+        // the same check as in delete_from_tail
+        if matches!(ret, Err(DeleteIfError::Empty)) && self.is_closed() {
+            fence(Ordering::SeqCst);
+            if self.head.load(Ordering::SeqCst).is_null() {
+                return Err(DeleteIfError::Closed);
+            }
+        }
+        ret
+    }
+
+    /// Takes up to n of the oldest values out of the list, oldest first. Everything but the last
+    /// node of the list is detached with a single descriptor and retired as one batch, the last
+    /// one empties the head as well and comes out on its own. In wait-free mode the values come
+    /// out one at a time. `Closed` only if not a single value was left in a closed list.
+    pub fn delete_many_from_tail(&self, n: usize) -> Result<Vec<T>, Closed> {
+        let mut values = Vec::new();
+        self.delete_many_into(n, &mut values)?;
+        Ok(values)
+    }
+
+    /// Like `delete_many_from_tail` but appends the values to a buffer the caller provides and
+    /// returns how many there were.
+    pub fn delete_many_into(&self, n: usize, buffer: &mut Vec<T>) -> Result<usize, Closed> {
+        let mut taken = 0;
+        while taken < n {
+            if self.announcements.is_none() {
+                // nodes whose value went to a remove are detached without handing out anything
+                let before = buffer.len();
+                let detached = self.delete_chain(n - taken, buffer);
+                if detached > 0 {
+                    self.length
+                        .fetch_sub(buffer.len() - before, Ordering::Relaxed);
+                    HazPtrHolder::try_reclaim();
+                    taken += buffer.len() - before;
+                    continue;
+                }
+            }
+            match self.delete_from_tail() {
+                Ok(Some(value)) => {
+                    buffer.push(value);
+                    taken += 1;
+                }
+                Ok(None) => break,
+                Err(closed) if taken == 0 => return Err(closed),
+                Err(_) => break,
+            }
+        }
+        Ok(taken)
+    }
+
+    /// Takes every value out of the list at once. Both ends of the list are held while it is
+    /// emptied, so other threads see it go from everything to nothing in one step, and the values
+    /// come out of the returned iterator in the order they were inserted.
+    pub fn take_all(&self) -> Drained<T> {
+        let node = self.drain().unwrap_or(core::ptr::null_mut());
+        // the nodes are ours now, the ones whose value went to a remove were taken off the length
+        // back then already
+        let mut remaining = 0;
+        let mut values = 0;
+        let mut walk = node;
+        while !walk.is_null() {
+            if unsafe { (*walk).state.load(Ordering::Acquire) } != REMOVED {
+                values += 1;
+            }
+            remaining += 1;
+            walk = unsafe { (*walk).prev.load(Ordering::Acquire) };
+        }
+        self.length.fetch_sub(values, Ordering::Relaxed);
+        HazPtrHolder::try_reclaim();
+        Drained {
+            node,
+            remaining,
+            deleter: self.deleter(),
+        }
+    }
+
+    /// Takes the oldest value the predicate holds for out of the list, wherever it is, and
+    /// unlinks its node unless it is at one of the ends. A node at the tail is unlinked by
+    /// whichever delete gets to it, one at the head by the next walk that finds it in the middle
+    /// of the list. The value stops counting towards `len`, `approx_len` and the capacity limit
+    /// right away. The list is walked from the tail and the walk starts over whenever the tail
+    /// moves, so the predicate may be asked about the same value more than once. A delete that
+    /// gets to a value while the predicate looks at it waits for the answer, so it should be
+    /// quick.
+    pub fn remove_first<F>(&self, mut predicate: F) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut removed = None;
+        self.walk(&mut |node| {
+            removed = node.remove_if(&mut predicate, &self.removed);
+            removed.is_none()
+        });
+        if removed.is_some() {
+            self.length.fetch_sub(1, Ordering::Relaxed);
+        }
+        HazPtrHolder::try_reclaim();
+        removed
+    }
+
+    /// Removes every value the predicate does not hold for, the same way `remove_first` removes
+    /// one. Values inserted while it runs may or may not be looked at.
+    pub fn retain<F>(&self, mut keep: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.walk(&mut |node| {
+            if node
+                .remove_if(&mut |value| !keep(value), &self.removed)
+                .is_some()
+            {
+                self.length.fetch_sub(1, Ordering::Relaxed);
+            }
+            true
+        });
+        HazPtrHolder::try_reclaim();
+    }
+
+    // Visits the nodes from the tail to the head for as long as visit returns true, and unlinks
+    // every node it comes across in the middle of the list that lost its value to a remove. Each
+    // node is protected before its prev is read, and we only move on if afterwards the node we
+    // came from is still linked and still points at it. Nothing can take that one out without
+    // unlinking the one we came from first, which moves the tail or sets its unlinked flag, and
+    // the node we started from can not come back as a new one while we protect it. Once the tail
+    // moves we can not tell how far, and start over from the new one.
+    fn walk(&self, visit: &mut dyn FnMut(&Node<T>) -> bool) {
+        let mut step = 0;
+        let mut start_holder = HazPtrHolder::default();
+        let mut behind_holder = HazPtrHolder::default();
+        let mut current = HazPtrHolder::default();
+        let mut spare = HazPtrHolder::default();
+        'restart: loop {
+            let Some(start) = (unsafe { start_holder.load(&self.tail) }) else {
+                return;
+            };
+            let start = start.keep();
+            let mut behind: *mut Node<T> = core::ptr::null_mut();
+            let mut node = start;
+            loop {
+                let more = visit(unsafe { &*node });
+                // the node is gone, we carry on from the one behind it
+                let from = if !behind.is_null()
+                    && unsafe { (*node).state.load(Ordering::Acquire) } == REMOVED
+                    && self.unlink(start, behind, node)
+                {
+                    behind
+                } else {
+                    node
+                };
+                if !more {
+                    return;
+                }
+                let next = unsafe { (*from).prev.load(Ordering::Acquire) };
+                if next.is_null() {
+                    return;
+                }
+                unsafe { spare.load(&AtomicPtr::new(next)) }
+                    .expect("Has to be there")
+                    .keep();
+                if self.tail.load(Ordering::SeqCst) != start
+                    || unsafe { (*from).unlinked.load(Ordering::SeqCst) }
+                    || unsafe { (*from).prev.load(Ordering::SeqCst) } != next
+                {
+                    sync::spin_loop();
+                    self.backoff.snooze(step);
+                    step = step.saturating_add(1);
+                    continue 'restart;
+                }
+                if from == node {
+                    core::mem::swap(&mut behind_holder, &mut current);
+                    behind = node;
+                }
+                core::mem::swap(&mut current, &mut spare);
+                node = next;
+            }
+        }
+    }
+
+    /// The number of values in the list at some point between the call and its return. The
+    /// indices of the tail and the head give the count of the nodes between them, and the tail is
+    /// read on both sides of the head so that the two belong to the same moment. A tail that
+    /// already moved to the node in front counts as the delete having happened, the value is only
+    /// handed out afterwards. While the last node is being deleted or a node is being linked into
+    /// an empty list the two ends do not line up, the first is helped to finish and the second is
+    /// waited out, which only takes the inserting thread a single store.
+    /// The nodes that lost their value to a remove are taken off the count, whether they are
+    /// still linked or only left a gap in the indices. The tail carries how many of them it got
+    /// past, and the number of removes is read on both sides of the ends and has to stay the same.
+    pub fn len(&self) -> usize {
+        let mut step = 0;
+        loop {
+            let removed = self.removed.load(Ordering::SeqCst);
+            let mut tail_holder = HazPtrHolder::default();
+            let tail_guard = unsafe { tail_holder.load(&self.tail) };
+            let mut head_holder = HazPtrHolder::default();
+            let head_guard = unsafe { head_holder.load(&self.head) };
+            let tail = tail_guard
+                .as_ref()
+                .map_or(core::ptr::null_mut(), |guard| guard.data);
+            let head = head_guard
+                .as_ref()
+                .map_or(core::ptr::null_mut(), |guard| guard.data);
+            if self.tail.load(Ordering::Acquire) == tail {
+                match (tail.is_null(), head.is_null()) {
+                    (true, true) => return 0,
+                    // a tail with nothing in front of it that is not the head as well was taken out
+                    // as the last node while a new one got linked in already. A head with a node
+                    // in front of it belongs to an insert halfway done, whose node a remove may
+                    // reach already
+                    (false, false)
+                        if (tail == head
+                            || !unsafe { (*tail).prev.load(Ordering::Acquire) }.is_null())
+                            && unsafe { (*head).prev.load(Ordering::Acquire) }.is_null() =>
+                    {
+                        let nodes = unsafe {
+                            let head_index = (*head).index.load(Ordering::Acquire);
+                            head_index.wrapping_sub((*tail).index.load(Ordering::Acquire)) + 1
+                        };
+                        let skipped = unsafe { (*tail).skipped.load(Ordering::Acquire) };
+                        if self.removed.load(Ordering::SeqCst) == removed {
+                            return nodes - (removed - skipped);
+                        }
+                    }
+                    (false, _) => self.help_head(),
+                    // the node linked into the empty list is still waiting for its tail, or a drain
+                    // emptied the tail and is about to do the same to the head
+                    (true, false) => self.help_head(),
+                }
+            }
+            core::mem::drop(head_guard);
+            core::mem::drop(tail_guard);
+            sync::spin_loop();
+            self.backoff.snooze(step);
+            step = step.saturating_add(1);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
-    pub fn length(&self) -> usize {
+    /// The counter that inserts, deletes, removes and cancels bump once they are done. It is a
+    /// single load but lags behind the list, so it can show a length the list never had while
+    /// others are working on it.
+    pub fn approx_len(&self) -> usize {
         self.length.load(Ordering::Relaxed)
     }
 }
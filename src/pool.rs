@@ -0,0 +1,222 @@
+#![allow(unexpected_cfgs)]
+use crate::hazard::{Deleter, Uniform};
+use crate::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+use alloc::alloc::{Layout, dealloc};
+use alloc::boxed::Box;
+use core::sync::atomic::Ordering;
+
+// Number of distinct layouts the pool keeps free lists for. Every pooled list needs two of them
+// (one for its nodes and one for its descriptors), anything beyond that is simply freed.
+const SLOTS: usize = 16;
+// Upper bound on the number of free blocks kept per layout so that a burst of deletions does not
+// pin memory forever.
+const CAPACITY: usize = 1024;
+
+#[cfg(not(loom))]
+static POOL: Pool = Pool {
+    slots: [const {
+        Slot {
+            layout: AtomicUsize::new(0),
+            head: AtomicPtr::new(core::ptr::null_mut()),
+            popping: AtomicBool::new(false),
+            len: AtomicUsize::new(0),
+        }
+    }; SLOTS],
+};
+
+#[cfg(loom)]
+loom::lazy_static! {
+    static ref POOL: Pool = Pool {
+        slots: core::array::from_fn(|_| Slot {
+            layout: AtomicUsize::new(0),
+            head: AtomicPtr::new(core::ptr::null_mut()),
+            popping: AtomicBool::new(false),
+            len: AtomicUsize::new(0),
+        }),
+    };
+}
+
+// A free block reuses the memory of the object that used to live there to store the link to the
+// next free block.
+struct Block {
+    next: *mut Block,
+}
+
+// Free list for a single layout. Pushing is a plain Treiber stack push. Popping is only ever done
+// by one thread at a time, guarded by the popping flag, which rules out the ABA problem: the block
+// we read as the head can not be popped and pushed back by somebody else while we hold the flag,
+// because nobody else is allowed to pop. A thread that finds the flag taken does not wait, it
+// just falls back to the allocator.
+struct Slot {
+    layout: AtomicUsize,
+    head: AtomicPtr<Block>,
+    popping: AtomicBool,
+    len: AtomicUsize,
+}
+
+struct Pool {
+    slots: [Slot; SLOTS],
+}
+
+impl Slot {
+    fn push(&self, block: *mut Block) -> bool {
+        if self.len.fetch_add(1, Ordering::SeqCst) >= CAPACITY {
+            self.len.fetch_sub(1, Ordering::SeqCst);
+            return false;
+        }
+        let mut current = self.head.load(Ordering::SeqCst);
+        loop {
+            unsafe { (*block).next = current };
+            match self
+                .head
+                .compare_exchange(current, block, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => return true,
+                Err(now) => current = now,
+            }
+        }
+    }
+
+    fn pop(&self) -> Option<*mut Block> {
+        if self
+            .popping
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return None;
+        }
+        let mut current = self.head.load(Ordering::SeqCst);
+        let ret = loop {
+            if current.is_null() {
+                break None;
+            }
+            let next = unsafe { (*current).next };
+            match self
+                .head
+                .compare_exchange(current, next, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => {
+                    self.len.fetch_sub(1, Ordering::SeqCst);
+                    break Some(current);
+                }
+                Err(now) => current = now,
+            }
+        };
+        self.popping.store(false, Ordering::SeqCst);
+        ret
+    }
+}
+
+impl Pool {
+    // The layout is packed into a single word so that a slot can be claimed with one CAS. Zero is
+    // never a valid key because pooled layouts are at least as large as a pointer.
+    fn key(layout: Layout) -> usize {
+        (layout.size() << 6) | layout.align().trailing_zeros() as usize
+    }
+
+    fn poolable(layout: Layout) -> bool {
+        layout.size() >= size_of::<Block>() && layout.align() >= align_of::<Block>()
+    }
+
+    fn find(&self, layout: Layout) -> Option<&Slot> {
+        let key = Self::key(layout);
+        self.slots
+            .iter()
+            .find(|slot| slot.layout.load(Ordering::SeqCst) == key)
+    }
+
+    fn find_or_claim(&self, layout: Layout) -> Option<&Slot> {
+        let key = Self::key(layout);
+        for slot in self.slots.iter() {
+            match slot
+                .layout
+                .compare_exchange(0, key, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => return Some(slot),
+                Err(current) if current == key => return Some(slot),
+                Err(_) => continue,
+            }
+        }
+        None
+    }
+}
+
+/// Moves the value into a recycled block if the pool has one of the right layout and into a fresh
+/// box otherwise. Either way the returned pointer can later be freed with `Box::from_raw`, so it
+/// does not matter to a deleter where the memory came from.
+pub(crate) fn alloc<T>(value: T) -> *mut T {
+    let layout = Layout::new::<T>();
+    if Pool::poolable(layout)
+        && let Some(slot) = POOL.find(layout)
+        && let Some(block) = slot.pop()
+    {
+        let ptr = block as *mut T;
+        unsafe { ptr.write(value) };
+        return ptr;
+    }
+    Box::into_raw(Box::new(value))
+}
+
+/// Hands a block that no thread can reach anymore back to the pool without dropping its contents.
+///
+/// # Safety
+///   The pointer must have been created by `alloc` or `Box::into_raw`, its contents must already
+///   have been dropped or moved out and no other thread may still access it.
+pub(crate) unsafe fn recycle_raw(ptr: *mut u8, layout: Layout) {
+    if Pool::poolable(layout)
+        && let Some(slot) = POOL.find_or_claim(layout)
+        && slot.push(ptr as *mut Block)
+    {
+        return;
+    }
+    unsafe { dealloc(ptr, layout) };
+}
+
+/// Drops the value behind the pointer and hands its memory back to the pool.
+///
+/// # Safety
+///   Same as `recycle_raw`, except that the contents are still alive and get dropped here.
+pub(crate) unsafe fn recycle<T>(ptr: *mut T) {
+    unsafe {
+        core::ptr::drop_in_place(ptr);
+        recycle_raw(ptr as *mut u8, Layout::new::<T>());
+    }
+}
+
+/// SAFETY:
+///   Recycle can be used as a deleter in place of DropBox for any pointer that was created using
+///   Box::into_raw(). Instead of deallocating the box after dropping its contents it hands the
+///   memory to a process wide pool, where the next allocation of the same layout picks it up. As
+///   the deleter is only ever called by the hazard domain once no hazard pointer protects the
+///   object anymore, a block is never reused while some thread can still read it.
+pub struct Recycle;
+
+impl Default for Recycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Recycle {
+    pub const fn new() -> Self {
+        Recycle
+    }
+}
+
+impl Deleter for Recycle {
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    fn delete(&self, ptr: *mut dyn Uniform) {
+        if ptr.is_null() {
+            return;
+        }
+        let layout = Layout::for_value(unsafe { &*ptr });
+        unsafe {
+            core::ptr::drop_in_place(ptr);
+            recycle_raw(ptr as *mut u8, layout);
+        }
+    }
+
+    fn pooled(&self) -> bool {
+        true
+    }
+}
@@ -1,11 +1,69 @@
 #![allow(unexpected_cfgs)]
+use core::ops::{Deref, DerefMut};
 
 #[cfg(loom)]
 pub mod atomic {
-    pub use loom::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+    pub use loom::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, fence};
 }
 
 #[cfg(not(loom))]
 pub mod atomic {
-    pub use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+    pub use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, fence};
+}
+
+// Gives up the time slice where there is a scheduler to give it to. Loom needs to see the yield to
+// explore the other threads instead of spinning forever.
+#[cfg(loom)]
+pub(crate) fn yield_now() {
+    loom::thread::yield_now();
+}
+
+#[cfg(all(not(loom), feature = "std"))]
+pub(crate) fn yield_now() {
+    std::thread::yield_now();
+}
+
+#[cfg(all(not(loom), not(feature = "std")))]
+pub(crate) fn yield_now() {
+    core::hint::spin_loop();
+}
+
+// A spin hint for loops that wait on a single store of another thread. Under loom it switches to
+// the other threads as well, otherwise the model never gets past a waiter whose peer got preempted.
+#[cfg(loom)]
+pub(crate) fn spin_loop() {
+    loom::hint::spin_loop();
+}
+
+#[cfg(not(loom))]
+pub(crate) fn spin_loop() {
+    core::hint::spin_loop();
+}
+
+/// Aligns and pads the wrapped value to its own cache line pair so that it never shares a line
+/// with a neighbouring field that other threads are hammering at the same time. 128 bytes covers
+/// the adjacent line prefetcher on x86_64 as well as the larger lines of some aarch64 cores.
+#[repr(align(128))]
+#[derive(Default)]
+pub struct CachePadded<T> {
+    value: T,
+}
+
+impl<T> CachePadded<T> {
+    pub const fn new(value: T) -> Self {
+        Self { value }
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
 }
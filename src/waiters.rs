@@ -0,0 +1,398 @@
+use crate::sync::CachePadded;
+use crate::sync::atomic::{AtomicPtr, AtomicUsize};
+use crate::{Closed, LinkedList};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::iter;
+use core::pin::Pin;
+use core::sync::atomic::Ordering;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+use std::sync::OnceLock;
+use std::thread::Thread;
+use std::time::Instant;
+
+// Number of slots in a block. A waiter that finds every slot of every block taken adds another
+// block, so no number of waiters ever has to wait for a slot, which a task polled on the same
+// thread as the other waiting tasks could not do.
+const SLOTS: usize = 64;
+
+// The states a slot goes through. The waiter moves it from FREE to CLAIMED, stores how to wake it
+// and marks itself WAITING. An insert that picks it moves it to WAKING while it takes a copy of
+// that out, and to NOTIFIED once it did, after which the waiter either waits again or gives the
+// slot back.
+const FREE: usize = 0;
+const CLAIMED: usize = 1;
+const WAITING: usize = 2;
+const WAKING: usize = 3;
+const NOTIFIED: usize = 4;
+
+// A parked thread or the waker of a pending task.
+#[derive(Clone)]
+enum Wake {
+    Thread(Thread),
+    Task(Waker),
+}
+
+impl Wake {
+    fn wake(self) {
+        match self {
+            Wake::Thread(thread) => thread.unpark(),
+            Wake::Task(waker) => waker.wake(),
+        }
+    }
+}
+
+pub(crate) struct Slot {
+    state: AtomicUsize,
+    // only written by the waiter while it holds the slot in CLAIMED
+    wake: UnsafeCell<Option<Wake>>,
+}
+
+// The waker is only written by the waiter while it holds the slot in CLAIMED and only read by an
+// insert while it holds the slot in WAKING.
+unsafe impl Sync for Slot {}
+
+// A block of slots, chained behind the ones that were there before it. Blocks are only freed
+// together with the list, so a slot stays where it is for as long as somebody may hold it.
+struct Segment {
+    slots: Box<[Slot]>,
+    next: AtomicPtr<Segment>,
+}
+
+impl Segment {
+    fn new() -> Self {
+        let slots: Vec<_> = (0..SLOTS)
+            .map(|_| Slot {
+                state: AtomicUsize::new(FREE),
+                wake: UnsafeCell::new(None),
+            })
+            .collect();
+        Self {
+            slots: slots.into_boxed_slice(),
+            next: AtomicPtr::new(core::ptr::null_mut()),
+        }
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        let next = self.next.load(Ordering::Acquire);
+        if !next.is_null() {
+            let _ = unsafe { Box::from_raw(next) };
+        }
+    }
+}
+
+// The threads and tasks waiting on an empty list. The slots are only allocated once a thread
+// actually has to wait, a list that is never waited on costs an insert the load of the counter.
+//
+// A waiter counts itself and arms its slot with SeqCst RMWs and only then looks at the head of the
+// list with a SeqCst load. Every insert into an empty list links its node with a SeqCst CAS of the
+// head before it loads the counter or picks a slot, so in the single order of all SeqCst operations
+// either the insert comes first and the waiter sees the node, or the waiter does and the insert
+// sees it waiting. Neither side needs a fence for that.
+pub(crate) struct Waiters {
+    waiting: CachePadded<AtomicUsize>,
+    first: OnceLock<Segment>,
+}
+
+impl Waiters {
+    pub(crate) fn new() -> Self {
+        Self {
+            waiting: CachePadded::new(AtomicUsize::new(0)),
+            first: OnceLock::new(),
+        }
+    }
+
+    fn segments(&self) -> impl Iterator<Item = &Segment> {
+        iter::successors(
+            Some(self.first.get_or_init(Segment::new)),
+            |segment| unsafe { segment.next.load(Ordering::Acquire).as_ref() },
+        )
+    }
+
+    fn slots(&self) -> impl Iterator<Item = &Slot> {
+        self.segments().flat_map(|segment| segment.slots.iter())
+    }
+
+    // Called after every insert, see above for why the single load is enough.
+    pub(crate) fn notify_one(&self) {
+        if self.waiting.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        for slot in self.slots() {
+            if slot
+                .state
+                .compare_exchange(WAITING, WAKING, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                let wake = unsafe { (*slot.wake.get()).clone() };
+                slot.state.store(NOTIFIED, Ordering::Release);
+                if let Some(wake) = wake {
+                    wake.wake();
+                }
+                return;
+            }
+        }
+    }
+
+    // Called by close. Every waiter gets picked, whoever arms its slot later looks at the list
+    // again and finds it closed.
+    pub(crate) fn notify_all(&self) {
+        if self.waiting.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        for slot in self.slots() {
+            if slot
+                .state
+                .compare_exchange(WAITING, WAKING, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                let wake = unsafe { (*slot.wake.get()).clone() };
+                slot.state.store(NOTIFIED, Ordering::Release);
+                if let Some(wake) = wake {
+                    wake.wake();
+                }
+            }
+        }
+    }
+
+    fn register(&self, wake: Wake) -> &Slot {
+        loop {
+            let mut last = None;
+            for segment in self.segments() {
+                for slot in segment.slots.iter() {
+                    if slot
+                        .state
+                        .compare_exchange(FREE, CLAIMED, Ordering::AcqRel, Ordering::Acquire)
+                        .is_ok()
+                    {
+                        unsafe { *slot.wake.get() = Some(wake) };
+                        self.waiting.fetch_add(1, Ordering::SeqCst);
+                        return slot;
+                    }
+                }
+                last = Some(segment);
+            }
+            // every slot is taken, so we add a block behind the last one. Whoever loses the race
+            // to add it throws its own away and takes a slot in the one that won
+            let new = Box::into_raw(Box::new(Segment::new()));
+            if last
+                .expect("There is always a first block")
+                .next
+                .compare_exchange(
+                    core::ptr::null_mut(),
+                    new,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_err()
+            {
+                let _ = unsafe { Box::from_raw(new) };
+            }
+        }
+    }
+
+    // Marks the slot as waiting again. The caller has to look at the list once more afterwards,
+    // an insert that finished before this saw the slot taken and left it alone. A slot that is
+    // still waiting was never picked, so every insert since it was armed left it alone as well.
+    fn arm(&self, slot: &Slot) {
+        loop {
+            match slot.state.load(Ordering::Acquire) {
+                WAITING => break,
+                WAKING => core::hint::spin_loop(),
+                state => {
+                    if slot
+                        .state
+                        .compare_exchange(state, WAITING, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_ok()
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    // Swaps the waker of a task that got polled again, which may come with a different one. The
+    // slot has to be held in CLAIMED for that, so the caller arms it again afterwards.
+    fn replace(&self, slot: &Slot, waker: &Waker) {
+        self.claim(slot);
+        let wake = unsafe { &mut *slot.wake.get() };
+        if !matches!(wake, Some(Wake::Task(current)) if current.will_wake(waker)) {
+            *wake = Some(Wake::Task(waker.clone()));
+        }
+    }
+
+    // Takes the slot back into CLAIMED from whatever state it is in, waiting out an insert that is
+    // taking a copy of the waker right now. Returns whether the slot had been notified.
+    fn claim(&self, slot: &Slot) -> bool {
+        loop {
+            match slot.state.load(Ordering::Acquire) {
+                WAKING => core::hint::spin_loop(),
+                state => {
+                    if slot
+                        .state
+                        .compare_exchange(state, CLAIMED, Ordering::AcqRel, Ordering::Acquire)
+                        .is_ok()
+                    {
+                        return state == NOTIFIED;
+                    }
+                }
+            }
+        }
+    }
+
+    // Parks until an insert picks the slot or the deadline passes, either of which may also
+    // happen spuriously. Returns false once the deadline has passed.
+    fn park(&self, slot: &Slot, deadline: Option<Instant>) -> bool {
+        loop {
+            match slot.state.load(Ordering::Acquire) {
+                NOTIFIED => return true,
+                WAKING => core::hint::spin_loop(),
+                _ => match deadline {
+                    None => {
+                        std::thread::park();
+                        return true;
+                    }
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            return false;
+                        }
+                        std::thread::park_timeout(deadline - now);
+                        return true;
+                    }
+                },
+            }
+        }
+    }
+
+    // Gives the slot back. A notification that arrived in the meantime may have been meant for a
+    // value we are not going to take, so it is handed on to the next waiter.
+    fn unregister(&self, slot: &Slot) {
+        let notified = self.claim(slot);
+        unsafe { *slot.wake.get() = None };
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+        slot.state.store(FREE, Ordering::Release);
+        if notified {
+            self.notify_one();
+        }
+    }
+}
+
+impl<T: 'static> LinkedList<T> {
+    /// Deletes from the tail, parking the thread for as long as the list is empty. Inserts wake
+    /// one parked thread each, and only look at the waiters at all while there are some. Returns
+    /// `Closed` once the list was closed and has nothing left.
+    pub fn pop_blocking(&self) -> Result<T, Closed> {
+        self.pop_until(None)
+            .map(|value| value.expect("Has to be there without a deadline"))
+    }
+
+    /// Same as `pop_blocking`, but gives up and returns None once the list stayed empty for the
+    /// whole of `timeout`.
+    pub fn pop_timeout(&self, timeout: Duration) -> Result<Option<T>, Closed> {
+        self.pop_until(Some(Instant::now() + timeout))
+    }
+
+    /// Deletes from the tail as a future that resolves once there was a value to take. While the
+    /// list is empty the waker of the task sits in the same slots the parked threads use and an
+    /// insert wakes it. Values are only ever taken in the poll that returns them, so dropping the
+    /// future loses nothing, and a wake-up it got but never acted on is passed on to the next
+    /// waiter.
+    pub fn pop_async(&self) -> PopFuture<'_, T> {
+        PopFuture {
+            list: self,
+            slot: None,
+        }
+    }
+
+    // Whether a waiter that armed its slot and found nothing to delete may go to sleep. A head
+    // without a tail belongs to an insert into the empty list that is about to publish its node,
+    // or to a drain, either of which is done in a moment.
+    fn still_empty(&self) -> bool {
+        self.head.load(Ordering::SeqCst).is_null()
+    }
+
+    fn pop_until(&self, deadline: Option<Instant>) -> Result<Option<T>, Closed> {
+        if let Some(value) = self.delete_from_tail()? {
+            return Ok(Some(value));
+        }
+        let slot = self.waiters.register(Wake::Thread(std::thread::current()));
+        loop {
+            self.waiters.arm(slot);
+            match self.delete_from_tail() {
+                Ok(None) => {}
+                ret => {
+                    self.waiters.unregister(slot);
+                    return ret;
+                }
+            }
+            if !self.still_empty() {
+                core::hint::spin_loop();
+                continue;
+            }
+            if !self.waiters.park(slot, deadline) {
+                self.waiters.unregister(slot);
+                // an insert may have come in right before we gave the slot back
+                return self.delete_from_tail();
+            }
+        }
+    }
+}
+
+/// The future returned by `LinkedList::pop_async`.
+pub struct PopFuture<'a, T: 'static> {
+    list: &'a LinkedList<T>,
+    // taken on the first poll that finds the list empty and held until the future is done
+    slot: Option<&'a Slot>,
+}
+
+impl<T: 'static> Future for PopFuture<'_, T> {
+    type Output = Result<T, Closed>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T, Closed>> {
+        let this = self.get_mut();
+        let waiters = &this.list.waiters;
+        if let Some(ret) = this.list.delete_from_tail().transpose() {
+            if let Some(slot) = this.slot.take() {
+                waiters.unregister(slot);
+            }
+            return Poll::Ready(ret);
+        }
+        let slot = match this.slot {
+            Some(slot) => {
+                waiters.replace(slot, cx.waker());
+                slot
+            }
+            None => *this
+                .slot
+                .insert(waiters.register(Wake::Task(cx.waker().clone()))),
+        };
+        loop {
+            waiters.arm(slot);
+            // an insert that finished before the slot was armed did not wake us
+            if let Some(ret) = this.list.delete_from_tail().transpose() {
+                this.slot = None;
+                waiters.unregister(slot);
+                return Poll::Ready(ret);
+            }
+            if this.list.still_empty() {
+                return Poll::Pending;
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+impl<T: 'static> Drop for PopFuture<'_, T> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            self.list.waiters.unregister(slot);
+        }
+    }
+}
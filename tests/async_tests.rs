@@ -0,0 +1,158 @@
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod async_test {
+    use ruby::list::LinkedList;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::Thread;
+    use std::time::Duration;
+
+    // Just enough of an executor to drive a single future on the current thread.
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(value) = future.as_mut().poll(&mut cx) {
+                return value;
+            }
+            std::thread::park();
+        }
+    }
+
+    struct CountWakes(AtomicUsize);
+
+    impl Wake for CountWakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn assert_send<F: Send>(_: &F) {}
+
+    #[test]
+    fn test_pop_async() {
+        const CONSUMERS: usize = 4;
+        const PER_CONSUMER: usize = 500;
+        let new = &LinkedList::new();
+        assert_send(&new.pop_async());
+        let taken = std::thread::scope(|s| {
+            let consumers: Vec<_> = (0..CONSUMERS)
+                .map(|_| {
+                    s.spawn(move || {
+                        (0..PER_CONSUMER)
+                            .map(|_| block_on(new.pop_async()).unwrap())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            for i in 0..CONSUMERS * PER_CONSUMER {
+                new.insert_from_head(i).unwrap();
+                if i % 100 == 0 {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }
+            consumers
+                .into_iter()
+                .map(|consumer| consumer.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        let mut taken = taken.concat();
+        taken.sort();
+        assert_eq!(taken, (0..CONSUMERS * PER_CONSUMER).collect::<Vec<_>>());
+        assert!(new.is_empty());
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let new = LinkedList::new();
+        let first_wakes = Arc::new(CountWakes(AtomicUsize::new(0)));
+        let second_wakes = Arc::new(CountWakes(AtomicUsize::new(0)));
+        let first_waker = Waker::from(first_wakes.clone());
+        let second_waker = Waker::from(second_wakes.clone());
+        let mut first = Box::pin(new.pop_async());
+        let mut second = Box::pin(new.pop_async());
+        assert!(
+            first
+                .as_mut()
+                .poll(&mut Context::from_waker(&first_waker))
+                .is_pending()
+        );
+        assert!(
+            second
+                .as_mut()
+                .poll(&mut Context::from_waker(&second_waker))
+                .is_pending()
+        );
+        new.insert_from_head(1).unwrap();
+        assert_eq!(
+            first_wakes.0.load(Ordering::SeqCst) + second_wakes.0.load(Ordering::SeqCst),
+            1
+        );
+        // whichever of the two got woken is dropped without being polled again, the value stays
+        // in the list and the other one is woken in its place
+        let (dropped, kept, kept_waker, kept_wakes) = if first_wakes.0.load(Ordering::SeqCst) == 1 {
+            (first, second, second_waker, second_wakes)
+        } else {
+            (second, first, first_waker, first_wakes)
+        };
+        drop(dropped);
+        assert_eq!(kept_wakes.0.load(Ordering::SeqCst), 1);
+        let mut kept = kept;
+        assert_eq!(
+            kept.as_mut().poll(&mut Context::from_waker(&kept_waker)),
+            Poll::Ready(Ok(1))
+        );
+        // a future dropped while still pending takes nothing with it
+        let mut pending = Box::pin(new.pop_async());
+        assert!(
+            pending
+                .as_mut()
+                .poll(&mut Context::from_waker(&kept_waker))
+                .is_pending()
+        );
+        drop(pending);
+        new.insert_from_head(2).unwrap();
+        assert_eq!(new.delete_from_tail(), Ok(Some(2)));
+    }
+
+    // More pending futures than a block has slots, all polled on the one thread. None of them may
+    // wait for a slot to come free, the thread that would free one is the one polling.
+    #[test]
+    fn test_many_pending() {
+        const FUTURES: usize = 200;
+        let new = LinkedList::new();
+        let wakes = Arc::new(CountWakes(AtomicUsize::new(0)));
+        let waker = Waker::from(wakes.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut futures: Vec<_> = (0..FUTURES).map(|_| Box::pin(new.pop_async())).collect();
+        for future in futures.iter_mut() {
+            assert!(future.as_mut().poll(&mut cx).is_pending());
+        }
+        for i in 0..FUTURES {
+            new.insert_from_head(i).unwrap();
+        }
+        assert_eq!(wakes.0.load(Ordering::SeqCst), FUTURES);
+        let mut taken: Vec<_> = futures
+            .iter_mut()
+            .map(|future| match future.as_mut().poll(&mut cx) {
+                Poll::Ready(Ok(value)) => value,
+                _ => panic!("every future was woken for a value"),
+            })
+            .collect();
+        taken.sort();
+        assert_eq!(taken, (0..FUTURES).collect::<Vec<_>>());
+        assert!(new.is_empty());
+    }
+}
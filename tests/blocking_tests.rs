@@ -0,0 +1,92 @@
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod blocking_test {
+    use ruby::list::LinkedList;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_pop_blocking() {
+        // the consumers start out on an empty list and have to be woken for every value
+        const CONSUMERS: usize = 4;
+        const PER_CONSUMER: usize = 500;
+        let new = &LinkedList::new();
+        let taken = std::thread::scope(|s| {
+            let consumers: Vec<_> = (0..CONSUMERS)
+                .map(|_| {
+                    s.spawn(move || {
+                        (0..PER_CONSUMER)
+                            .map(|_| new.pop_blocking().unwrap())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            for i in 0..CONSUMERS * PER_CONSUMER {
+                new.insert_from_head(i).unwrap();
+                if i % 100 == 0 {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            }
+            consumers
+                .into_iter()
+                .map(|consumer| consumer.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        let mut taken = taken.concat();
+        taken.sort();
+        assert_eq!(taken, (0..CONSUMERS * PER_CONSUMER).collect::<Vec<_>>());
+        assert!(new.is_empty());
+    }
+
+    #[test]
+    fn test_pop_timeout() {
+        let new = &LinkedList::new();
+        let start = Instant::now();
+        assert_eq!(new.pop_timeout(Duration::from_millis(50)), Ok(None));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        new.insert_from_head(1).unwrap();
+        assert_eq!(new.pop_timeout(Duration::from_millis(50)), Ok(Some(1)));
+        // a value that comes in while the thread is parked ends the wait early
+        let value = std::thread::scope(|s| {
+            let consumer = s.spawn(move || new.pop_timeout(Duration::from_secs(60)));
+            std::thread::sleep(Duration::from_millis(20));
+            new.insert_from_head(2).unwrap();
+            consumer.join().unwrap()
+        });
+        assert_eq!(value, Ok(Some(2)));
+    }
+
+    #[test]
+    fn test_timeouts_lose_nothing() {
+        // consumers keep timing out while values trickle in, whatever they did not take has to
+        // still be in the list
+        let new = &LinkedList::new();
+        let taken = std::thread::scope(|s| {
+            let consumers: Vec<_> = (0..4)
+                .map(|_| {
+                    s.spawn(move || {
+                        let mut taken = Vec::new();
+                        for _ in 0..200 {
+                            if let Ok(Some(value)) = new.pop_timeout(Duration::from_micros(50)) {
+                                taken.push(value);
+                            }
+                        }
+                        taken
+                    })
+                })
+                .collect();
+            for i in 0..400 {
+                new.insert_from_head(i).unwrap();
+            }
+            consumers
+                .into_iter()
+                .map(|consumer| consumer.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        let mut taken = taken.concat();
+        while let Ok(Some(value)) = new.delete_from_tail() {
+            taken.push(value);
+        }
+        taken.sort();
+        assert_eq!(taken, (0..400).collect::<Vec<_>>());
+    }
+}
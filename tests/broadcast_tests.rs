@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod broadcast_test {
+    use ruby::HazPtrHolder;
+    use ruby::broadcast::{Broadcast, TryRecvError};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Clone)]
+    struct Count(Arc<AtomicUsize>);
+    impl Drop for Count {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_every_subscriber_sees_everything() {
+        const SUBSCRIBERS: usize = 4;
+        const VALUES: usize = 2000;
+        let broadcast = &Broadcast::new();
+        let subscribers: Vec<_> = (0..SUBSCRIBERS)
+            .map(|_| broadcast.subscribe().unwrap())
+            .collect();
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..VALUES {
+                    broadcast.publish(i);
+                }
+            });
+            for mut subscriber in subscribers {
+                s.spawn(move || {
+                    let mut seen = Vec::new();
+                    while seen.len() < VALUES {
+                        match subscriber.try_recv() {
+                            Ok(value) => seen.push(value),
+                            Err(TryRecvError::Empty) => std::thread::yield_now(),
+                            Err(lagged) => panic!("no lag limit, got {:?}", lagged),
+                        }
+                    }
+                    assert_eq!(seen, (0..VALUES).collect::<Vec<_>>());
+                    assert_eq!(subscriber.try_recv(), Err(TryRecvError::Empty));
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_late_subscriber() {
+        let broadcast = Broadcast::new();
+        let mut early = broadcast.subscribe().unwrap();
+        broadcast.publish(0);
+        let mut late = broadcast.subscribe().unwrap();
+        broadcast.publish(1);
+        assert_eq!(early.try_recv(), Ok(0));
+        assert_eq!(early.try_recv(), Ok(1));
+        assert_eq!(late.try_recv(), Ok(1));
+        assert_eq!(late.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn test_lag_limit() {
+        let broadcast = Broadcast::with_lag_limit(10);
+        let mut fast = broadcast.subscribe().unwrap();
+        let mut slow = broadcast.subscribe().unwrap();
+        for i in 0..100 {
+            broadcast.publish(i);
+            assert_eq!(fast.try_recv(), Ok(i));
+        }
+        let mut seen = Vec::new();
+        let mut skipped = 0;
+        loop {
+            match slow.try_recv() {
+                Ok(value) => seen.push(value),
+                Err(TryRecvError::Lagged(n)) => skipped += n,
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+        assert!(skipped > 0);
+        assert!(seen.len() <= 11);
+        assert_eq!(seen.len() + skipped, 100);
+        assert_eq!(seen, (100 - seen.len()..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_retired_once_passed() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let broadcast = Broadcast::new();
+        let mut subscriber = broadcast.subscribe().unwrap();
+        for _ in 0..10 {
+            broadcast.publish(Count(drops.clone()));
+        }
+        HazPtrHolder::try_reclaim();
+        // nothing the subscriber still has to see goes away
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+        for _ in 0..10 {
+            drop(subscriber.try_recv().ok().unwrap());
+        }
+        assert_eq!(drops.load(Ordering::SeqCst), 10);
+        drop(subscriber);
+        broadcast.publish(Count(drops.clone()));
+        HazPtrHolder::try_reclaim();
+        // everything but the last value published is gone once nobody needs it
+        assert_eq!(drops.load(Ordering::SeqCst), 20);
+        drop(broadcast);
+        assert_eq!(drops.load(Ordering::SeqCst), 21);
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod channel_test {
+    #[cfg(feature = "std")]
+    use ruby::channel::{RecvError, RecvTimeoutError};
+    use ruby::channel::{SendError, TryRecvError, channel};
+
+    #[test]
+    fn test_try_recv() {
+        let (sender, receiver) = channel();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        for i in 0..4 {
+            sender.send(i).unwrap();
+        }
+        let second = sender.clone();
+        drop(sender);
+        second.send(4).unwrap();
+        // the list stays open while a clone of the sender is around
+        for i in 0..5 {
+            assert_eq!(receiver.try_recv(), Ok(i));
+        }
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        second.send(5).unwrap();
+        drop(second);
+        assert_eq!(receiver.try_recv(), Ok(5));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn test_receivers_gone() {
+        let (sender, receiver) = channel();
+        let second = receiver.clone();
+        drop(receiver);
+        sender.send(0).unwrap();
+        drop(second);
+        assert_eq!(sender.send(1), Err(SendError(1)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_recv() {
+        const SENDERS: usize = 4;
+        const PER_SENDER: usize = 500;
+        let (sender, receiver) = channel();
+        let taken = std::thread::scope(|s| {
+            let receivers: Vec<_> = (0..2)
+                .map(|_| {
+                    let receiver = receiver.clone();
+                    s.spawn(move || {
+                        let mut taken = Vec::new();
+                        while let Ok(value) = receiver.recv() {
+                            taken.push(value);
+                        }
+                        taken
+                    })
+                })
+                .collect();
+            for t in 0..SENDERS {
+                let sender = sender.clone();
+                s.spawn(move || {
+                    for i in 0..PER_SENDER {
+                        sender.send(t * PER_SENDER + i).unwrap();
+                    }
+                });
+            }
+            drop(sender);
+            receivers
+                .into_iter()
+                .map(|receiver| receiver.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        let mut taken = taken.concat();
+        taken.sort();
+        assert_eq!(taken, (0..SENDERS * PER_SENDER).collect::<Vec<_>>());
+        assert_eq!(receiver.recv(), Err(RecvError));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_recv_timeout() {
+        let (sender, receiver) = channel();
+        assert_eq!(
+            receiver.recv_timeout(std::time::Duration::from_millis(20)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                sender.send(1).unwrap();
+            });
+            assert_eq!(
+                receiver.recv_timeout(std::time::Duration::from_secs(60)),
+                Ok(1)
+            );
+            assert_eq!(
+                receiver.recv_timeout(std::time::Duration::from_secs(60)),
+                Err(RecvTimeoutError::Disconnected)
+            );
+        });
+    }
+}
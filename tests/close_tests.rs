@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod close_test {
+    use ruby::list::{Closed, LinkedList};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_close() {
+        let new = LinkedList::new();
+        for i in 0..4 {
+            new.insert_from_head(i).unwrap();
+        }
+        assert!(!new.is_closed());
+        new.close();
+        assert!(new.is_closed());
+        assert_eq!(new.insert_from_head(4), Err(4));
+        assert_eq!(new.try_insert_from_head(5), Err(5));
+        for i in 0..4 {
+            assert_eq!(new.delete_from_tail(), Ok(Some(i)));
+        }
+        assert_eq!(new.delete_from_tail(), Err(Closed));
+        assert_eq!(new.delete_from_tail(), Err(Closed));
+        // closing twice changes nothing
+        new.close();
+        assert_eq!(new.delete_from_tail(), Err(Closed));
+        assert!(new.is_empty());
+    }
+
+    #[test]
+    fn test_close_wait_free() {
+        let new = LinkedList::new().wait_free();
+        new.insert_from_head(0).unwrap();
+        new.close();
+        assert_eq!(new.insert_from_head(1), Err(1));
+        assert_eq!(new.delete_from_tail(), Ok(Some(0)));
+        assert_eq!(new.delete_from_tail(), Err(Closed));
+    }
+
+    #[test]
+    fn test_close_releases_full_producer() {
+        let new = &LinkedList::with_capacity_limit(1);
+        new.insert_from_head(0).unwrap();
+        std::thread::scope(|s| {
+            let producer = s.spawn(move || new.insert_from_head(1));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            new.close();
+            assert_eq!(producer.join().unwrap(), Err(1));
+        });
+        assert_eq!(new.delete_from_tail(), Ok(Some(0)));
+        assert_eq!(new.delete_from_tail(), Err(Closed));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_close_wakes_waiters() {
+        let new = &LinkedList::<usize>::new();
+        std::thread::scope(|s| {
+            let consumers: Vec<_> = (0..4)
+                .map(|_| s.spawn(move || new.pop_blocking()))
+                .collect();
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            new.close();
+            for consumer in consumers {
+                assert_eq!(consumer.join().unwrap(), Err(Closed));
+            }
+        });
+        assert_eq!(
+            new.pop_timeout(std::time::Duration::from_secs(60)),
+            Err(Closed)
+        );
+    }
+
+    #[test]
+    fn test_close_races_inserts() {
+        // every insert either hands its value back or gets it to a consumer, a value that made it
+        // in after the consumers were told the list is closed would be left behind
+        for _ in 0..20 {
+            let new = &LinkedList::new();
+            let inserted = &AtomicUsize::new(0);
+            let taken = &AtomicUsize::new(0);
+            std::thread::scope(|s| {
+                for t in 0..4 {
+                    s.spawn(move || {
+                        for i in 0..500 {
+                            if new.insert_from_head(t * 500 + i).is_ok() {
+                                inserted.fetch_add(1, Ordering::SeqCst);
+                            }
+                        }
+                    });
+                }
+                for _ in 0..2 {
+                    s.spawn(move || {
+                        loop {
+                            match new.delete_from_tail() {
+                                Ok(Some(_)) => {
+                                    taken.fetch_add(1, Ordering::SeqCst);
+                                }
+                                Ok(None) => std::thread::yield_now(),
+                                Err(Closed) => break,
+                            }
+                        }
+                    });
+                }
+                std::thread::yield_now();
+                new.close();
+            });
+            assert_eq!(new.delete_from_tail(), Err(Closed));
+            assert_eq!(
+                inserted.load(Ordering::SeqCst),
+                taken.load(Ordering::SeqCst)
+            );
+        }
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod combining_test {
+    use ruby::CombiningList;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_combining() {
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 2000;
+        let new = &CombiningList::new();
+        let remaining = &AtomicUsize::new(PRODUCERS * PER_PRODUCER);
+        let taken = std::thread::scope(|s| {
+            for p in 0..PRODUCERS {
+                s.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        new.insert_from_head(p * PER_PRODUCER + i).unwrap();
+                    }
+                });
+            }
+            let consumers: Vec<_> = (0..PRODUCERS)
+                .map(|_| {
+                    s.spawn(move || {
+                        let mut taken = Vec::new();
+                        while remaining.load(Ordering::SeqCst) > 0 {
+                            if let Ok(Some(value)) = new.delete_from_tail() {
+                                remaining.fetch_sub(1, Ordering::SeqCst);
+                                taken.push(value);
+                            }
+                        }
+                        taken
+                    })
+                })
+                .collect();
+            consumers
+                .into_iter()
+                .map(|consumer| consumer.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        for values in taken.iter() {
+            for p in 0..PRODUCERS {
+                let from_producer: Vec<_> = values
+                    .iter()
+                    .filter(|value| **value / PER_PRODUCER == p)
+                    .collect();
+                assert!(from_producer.windows(2).all(|pair| pair[0] < pair[1]));
+            }
+        }
+        let mut taken = taken.concat();
+        taken.sort();
+        assert_eq!(taken, (0..PRODUCERS * PER_PRODUCER).collect::<Vec<_>>());
+        assert_eq!(0_usize, new.len());
+        assert!(new.is_empty());
+        assert_eq!(0_usize, new.approx_len());
+    }
+
+    #[test]
+    fn test_drop_with_values() {
+        let drops = AtomicUsize::new(0);
+        struct Count<'a>(&'a AtomicUsize);
+        impl Drop for Count<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        let list = CombiningList::with_pool();
+        for _ in 0..10 {
+            assert!(list.insert_from_head(Count(&drops)).is_ok());
+        }
+        drop(list.delete_from_tail());
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+        assert_eq!(list.len(), 9);
+        drop(list);
+        assert_eq!(drops.load(Ordering::SeqCst), 10);
+    }
+
+    #[test]
+    fn test_close() {
+        use ruby::list::Closed;
+        let list = CombiningList::new();
+        list.insert_from_head(0).unwrap();
+        assert!(!list.is_closed());
+        list.close();
+        assert!(list.is_closed());
+        assert_eq!(list.insert_from_head(1), Err(1));
+        assert_eq!(list.delete_from_tail(), Ok(Some(0)));
+        assert_eq!(list.delete_from_tail(), Err(Closed));
+        assert!(list.is_empty());
+    }
+}
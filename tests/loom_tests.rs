@@ -5,6 +5,8 @@
 mod loom_tests {
     use loom::sync::Arc;
     use ruby::list::LinkedList;
+    use ruby::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
     #[test]
     fn concurrency_test() {
         loom::model(|| {
@@ -12,15 +14,326 @@ mod loom_tests {
             let cloned1 = Arc::clone(&new);
             let cloned2 = Arc::clone(&new);
             let t1 = loom::thread::spawn(move || {
-                cloned1.insert_from_head(2);
+                cloned1.insert_from_head(2).unwrap();
+            });
+            let t2 = loom::thread::spawn(move || {
+                let _ = cloned2.delete_from_tail();
+            });
+            t1.join().unwrap();
+            t2.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn multi_insert() {
+        loom::model(|| {
+            let new = Arc::new(LinkedList::new());
+            let threads: Vec<_> = (0..2)
+                .map(|i| {
+                    let cloned = Arc::clone(&new);
+                    loom::thread::spawn(move || {
+                        cloned.insert_from_head(i).unwrap();
+                    })
+                })
+                .collect();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+            assert_eq!(new.len(), 2);
+            let mut taken = vec![
+                new.delete_from_tail().unwrap(),
+                new.delete_from_tail().unwrap(),
+            ];
+            taken.sort();
+            assert_eq!(taken, vec![Some(0), Some(1)]);
+            assert_eq!(new.delete_from_tail(), Ok(None));
+        });
+    }
+
+    #[test]
+    fn multi_delete() {
+        bounded().check(|| {
+            let new = Arc::new(LinkedList::new());
+            new.insert_from_head(0).unwrap();
+            new.insert_from_head(1).unwrap();
+            let threads: Vec<_> = (0..2)
+                .map(|_| {
+                    let cloned = Arc::clone(&new);
+                    loom::thread::spawn(move || cloned.delete_from_tail())
+                })
+                .collect();
+            let mut taken: Vec<_> = threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap().unwrap())
+                .collect();
+            taken.sort();
+            assert_eq!(taken, vec![Some(0), Some(1)]);
+            assert_eq!(new.len(), 0);
+        });
+    }
+
+    // The delete follows the insert on the same thread, so the list never holds fewer than the
+    // value it started with and never more than two, whatever len reads on the way.
+    #[test]
+    fn len_test() {
+        bounded().check(|| {
+            let new = Arc::new(LinkedList::new());
+            new.insert_from_head(0).unwrap();
+            let cloned = Arc::clone(&new);
+            let t1 = loom::thread::spawn(move || {
+                cloned.insert_from_head(1).unwrap();
+                let _ = cloned.delete_from_tail();
+            });
+            let len = new.len();
+            assert!(len == 1 || len == 2);
+            t1.join().unwrap();
+            assert_eq!(new.len(), 1);
+        });
+    }
+
+    // Every value has to be dropped exactly once, no matter whether it was taken out by a delete
+    // or was still in the list when the last handle went away on another thread.
+    #[test]
+    fn drop_while_working() {
+        loom::model(|| {
+            let drops = Arc::new(AtomicUsize::new(0));
+            let new = Arc::new(LinkedList::new());
+            let cloned1 = Arc::clone(&new);
+            let cloned2 = Arc::clone(&new);
+            let count = drops.clone();
+            let t1 = loom::thread::spawn(move || {
+                assert!(cloned1.insert_from_head(CountDrops(count)).is_ok());
             });
             let t2 = loom::thread::spawn(move || {
                 let _ = cloned2.delete_from_tail();
             });
+            drop(new);
             t1.join().unwrap();
             t2.join().unwrap();
+            assert_eq!(drops.load(Ordering::Relaxed), 1);
+        });
+    }
+
+    // An insert into the empty list does not go through the head descriptor, so it has to undo
+    // itself when the close slipped in between. Once a delete reported the list closed, the
+    // insert must have been handed its value back.
+    #[test]
+    fn close_test() {
+        bounded().check(|| {
+            let new = Arc::new(LinkedList::new());
+            let cloned = Arc::clone(&new);
+            let t1 = loom::thread::spawn(move || cloned.insert_from_head(1));
+            new.close();
+            let first = new.delete_from_tail();
+            let inserted = t1.join().unwrap();
+            match first {
+                Err(_) => assert_eq!(inserted, Err(1)),
+                Ok(Some(value)) => assert_eq!(value, 1),
+                Ok(None) => {
+                    if inserted.is_ok() {
+                        assert_eq!(new.delete_from_tail(), Ok(Some(1)));
+                    }
+                }
+            }
+            assert!(new.delete_from_tail().is_err());
+        });
+    }
+
+    // A batch goes in with one descriptor while the delete takes the value that was there before
+    // it, and the batch comes out in order afterwards. Both sides go through several helping
+    // steps, with two preemptions the run does not finish in reasonable time, so it gets one.
+    #[test]
+    fn batch_test() {
+        let mut builder = bounded();
+        builder.preemption_bound = Some(1);
+        builder.check(|| {
+            let new = Arc::new(LinkedList::new());
+            new.insert_from_head(0).unwrap();
+            let cloned = Arc::clone(&new);
+            let t1 = loom::thread::spawn(move || cloned.insert_batch_from_head(1..3).unwrap());
+            let first = new.delete_from_tail().unwrap();
+            t1.join().unwrap();
+            assert_eq!(first, Some(0));
+            assert_eq!(new.len(), 2);
+            assert_eq!(new.delete_from_tail(), Ok(Some(1)));
+            assert_eq!(new.delete_from_tail(), Ok(Some(2)));
+            assert_eq!(new.delete_from_tail(), Ok(None));
         });
     }
+
+    // A batch delete and a single delete going after the same tail. The values come out once
+    // each, the ones moved out of the detached nodes are not dropped a second time when those
+    // get reclaimed, and the one left behind goes with the list. Two preemptions take minutes
+    // here, one is enough to have either delete land in the middle of the other.
+    #[test]
+    fn delete_many_test() {
+        let mut builder = bounded();
+        builder.preemption_bound = Some(1);
+        builder.check(|| {
+            let drops = Arc::new(AtomicUsize::new(0));
+            let new = Arc::new(LinkedList::new());
+            for _ in 0..4 {
+                assert!(new.insert_from_head(CountDrops(drops.clone())).is_ok());
+            }
+            let cloned = Arc::clone(&new);
+            let t1 = loom::thread::spawn(move || cloned.delete_many_from_tail(2).ok().unwrap());
+            let single = new.delete_from_tail().ok().unwrap();
+            let batch = t1.join().unwrap();
+            assert!(single.is_some());
+            assert_eq!(batch.len(), 2);
+            assert_eq!(new.len(), 1);
+            drop(single);
+            drop(batch);
+            assert_eq!(drops.load(Ordering::Relaxed), 3);
+            drop(new);
+            assert_eq!(drops.load(Ordering::Relaxed), 4);
+        });
+    }
+
+    // A drain racing an insert and a delete. Every value comes out exactly once, through the
+    // drain in the order it went in, through the delete, or from what is left afterwards.
+    #[test]
+    fn take_all_test() {
+        let mut builder = bounded();
+        builder.preemption_bound = Some(1);
+        builder.check(|| {
+            let new = Arc::new(LinkedList::new());
+            new.insert_from_head(0).unwrap();
+            let cloned = Arc::clone(&new);
+            let t1 = loom::thread::spawn(move || {
+                cloned.insert_from_head(1).unwrap();
+                cloned.delete_from_tail().unwrap()
+            });
+            let drained: Vec<_> = new.take_all().collect();
+            assert!(drained.windows(2).all(|pair| pair[0] < pair[1]));
+            let mut taken = drained;
+            taken.extend(t1.join().unwrap());
+            taken.extend(new.take_all());
+            taken.sort();
+            assert_eq!(taken, vec![0, 1]);
+            assert_eq!(new.len(), 0);
+        });
+    }
+
+    // A conditional delete that only takes the first value racing a plain delete. Whichever
+    // gets there first takes it, and the conditional one never takes the second value.
+    #[test]
+    fn delete_if_test() {
+        bounded().check(|| {
+            let new = Arc::new(LinkedList::new());
+            new.insert_from_head(0).unwrap();
+            new.insert_from_head(1).unwrap();
+            let cloned = Arc::clone(&new);
+            let t1 = loom::thread::spawn(move || cloned.delete_from_tail_if(|&value| value == 0));
+            let plain = new.delete_from_tail().unwrap();
+            match t1.join().unwrap() {
+                Ok(value) => {
+                    assert_eq!(value, 0);
+                    assert_eq!(plain, Some(1));
+                }
+                Err(error) => {
+                    assert_eq!(error, ruby::DeleteIfError::Rejected);
+                    assert_eq!(plain, Some(0));
+                    assert_eq!(new.delete_from_tail(), Ok(Some(1)));
+                }
+            }
+            assert_eq!(new.len(), 0);
+        });
+    }
+
+    // A remove racing a delete for the oldest value. Every value comes out exactly once, and a
+    // delete that finds the value removed unlinks the node and goes on to the next one.
+    #[test]
+    fn remove_first_test() {
+        bounded().check(|| {
+            let new = Arc::new(LinkedList::new());
+            new.insert_from_head(0).unwrap();
+            new.insert_from_head(1).unwrap();
+            let cloned = Arc::clone(&new);
+            let t1 = loom::thread::spawn(move || cloned.remove_first(|_| true));
+            let deleted = new.delete_from_tail().unwrap();
+            let removed = t1.join().unwrap();
+            let mut taken: Vec<_> = removed.into_iter().chain(deleted).collect();
+            taken.extend(new.take_all());
+            taken.sort();
+            assert_eq!(taken, vec![0, 1]);
+            assert_eq!(new.len(), 0);
+        });
+    }
+
+    // A cancel racing a delete for the same value, exactly one of them gets it and the delete
+    // goes on to the next value if the cancel was first.
+    #[test]
+    fn cancel_test() {
+        bounded().check(|| {
+            let new = Arc::new(LinkedList::new());
+            let handle = new.insert_from_head_with_handle(0).unwrap();
+            new.insert_from_head(1).unwrap();
+            let cloned = Arc::clone(&new);
+            let t1 = loom::thread::spawn(move || cloned.delete_from_tail().unwrap());
+            let cancelled = handle.try_cancel();
+            let deleted = t1.join().unwrap();
+            let left: Vec<_> = new.take_all().collect();
+            match deleted {
+                Some(0) => {
+                    assert_eq!(cancelled, None);
+                    assert_eq!(left, vec![1]);
+                }
+                deleted => {
+                    assert_eq!(deleted, Some(1));
+                    assert_eq!(cancelled, Some(0));
+                    assert!(left.is_empty());
+                }
+            }
+        });
+    }
+
+    // With a lag limit of one the publisher retires the nodes right under the subscriber, which
+    // has to notice before it reads a node that is gone and must never see values out of order.
+    #[test]
+    fn broadcast_test() {
+        bounded().check(|| {
+            let broadcast = Arc::new(ruby::Broadcast::with_lag_limit(1));
+            let mut subscriber = broadcast.subscribe().unwrap();
+            let cloned = Arc::clone(&broadcast);
+            let t1 = loom::thread::spawn(move || {
+                for i in 0..3 {
+                    cloned.publish(i);
+                }
+            });
+            let mut last = None;
+            let mut accounted = 0;
+            for _ in 0..3 {
+                match subscriber.try_recv() {
+                    Ok(value) => {
+                        assert!(last < Some(value));
+                        last = Some(value);
+                        accounted += 1;
+                    }
+                    Err(ruby::broadcast::TryRecvError::Lagged(skipped)) => accounted += skipped,
+                    Err(ruby::broadcast::TryRecvError::Empty) => {}
+                }
+            }
+            t1.join().unwrap();
+            assert!(accounted <= 3);
+        });
+    }
+
+    // The retry loops of two threads going after the same end make for long paths, so these
+    // runs get more room per path and fewer preemptions to explore in exchange.
+    fn bounded() -> loom::model::Builder {
+        let mut builder = loom::model::Builder::new();
+        builder.max_branches = 100_000;
+        builder.preemption_bound = Some(2);
+        builder
+    }
+
+    struct CountDrops(Arc<AtomicUsize>);
+    impl Drop for CountDrops {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,30 @@
+// Run with `cargo test --no-default-features --test no_std` to check that the list and the hazard
+// domain work when the library is built against core and alloc only. The harness itself still
+// needs std, which is the only reason it is linked here.
+#![no_std]
+
+extern crate alloc;
+extern crate std;
+
+#[cfg(test)]
+mod no_std_test {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use ruby::hazard::HazPtrHolder;
+    use ruby::list::LinkedList;
+
+    #[test]
+    fn insert_and_delete_without_std() {
+        let list = LinkedList::new();
+        for i in 0..10 {
+            list.insert_from_head(alloc::format!("{}", i)).unwrap();
+        }
+        assert_eq!(list.len(), 10);
+        let taken: Vec<String> = core::iter::from_fn(|| list.delete_from_tail().unwrap()).collect();
+        assert_eq!(taken.len(), 10);
+        assert_eq!(taken[0], "0");
+        assert_eq!(taken[9], "9");
+        assert_eq!(list.len(), 0);
+        HazPtrHolder::try_reclaim();
+    }
+}
@@ -0,0 +1,165 @@
+#![allow(unexpected_cfgs)]
+
+#[cfg(test)]
+#[cfg(not(loom))]
+mod panic_test {
+    use ruby::hazard::{DropBox, HazPtrHolder, HazPtrObject};
+    use ruby::list::LinkedList;
+    use ruby::sync::atomic::AtomicPtr;
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // the hazard domain is shared by the whole process, so a test that leaves a panicking object
+    // on the retired list must not run alongside another one that might reclaim it
+    static SERIAL: Mutex<()> = Mutex::new(());
+    static DROPBOX: DropBox = DropBox::new();
+
+    struct Bomb {
+        id: usize,
+        armed: bool,
+        drops: &'static AtomicUsize,
+    }
+
+    impl Drop for Bomb {
+        fn drop(&mut self) {
+            self.drops.fetch_add(1, Ordering::SeqCst);
+            if self.armed {
+                panic!("bomb {} went off", self.id);
+            }
+        }
+    }
+
+    #[test]
+    fn panic_while_reclaiming_in_delete_from_tail() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        let list = LinkedList::new();
+        for id in 0..3 {
+            assert!(
+                list.insert_from_head(Bomb {
+                    id,
+                    armed: false,
+                    drops: &DROPS,
+                })
+                .is_ok()
+            );
+        }
+        // retire a few objects while they are still protected so that they stay on the retired
+        // list until the next reclamation pass, which happens inside delete_from_tail
+        let atomics: Vec<AtomicPtr<Bomb>> = (0..4)
+            .map(|id| {
+                AtomicPtr::new(Box::into_raw(Box::new(Bomb {
+                    id: 10 + id,
+                    armed: id == 1,
+                    drops: &DROPS,
+                })))
+            })
+            .collect();
+        let mut holders: Vec<HazPtrHolder> = (0..4).map(|_| HazPtrHolder::default()).collect();
+        let guards: Vec<_> = holders
+            .iter_mut()
+            .zip(&atomics)
+            .map(|(holder, atomic)| unsafe { holder.load(atomic) })
+            .collect();
+        for atomic in &atomics {
+            let mut holder = HazPtrHolder::default();
+            if let Some(mut wrapper) =
+                unsafe { holder.swap(atomic, std::ptr::null_mut(), &DROPBOX) }
+            {
+                wrapper.retire();
+            }
+        }
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+        std::mem::drop(guards);
+
+        let result = catch_unwind(AssertUnwindSafe(|| list.delete_from_tail()));
+        assert!(result.is_err());
+        // the popped value was dropped while unwinding, the armed bomb exactly once and whatever
+        // was not reached yet must still be on the retired list
+        HazPtrHolder::try_reclaim();
+        assert_eq!(DROPS.load(Ordering::SeqCst), 5);
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(
+            list.delete_from_tail().unwrap().map(|bomb| bomb.id),
+            Some(1)
+        );
+        assert_eq!(
+            list.delete_from_tail().unwrap().map(|bomb| bomb.id),
+            Some(2)
+        );
+        assert!(list.delete_from_tail().unwrap().is_none());
+        std::mem::drop(list);
+        HazPtrHolder::try_reclaim();
+        assert_eq!(DROPS.load(Ordering::SeqCst), 7);
+    }
+
+    #[test]
+    fn panic_while_dropping_the_list() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        let list = LinkedList::new();
+        for id in 0..5 {
+            assert!(
+                list.insert_from_head(Bomb {
+                    id,
+                    armed: id == 2,
+                    drops: &DROPS,
+                })
+                .is_ok()
+            );
+        }
+        assert_eq!(
+            list.delete_from_tail().unwrap().map(|bomb| bomb.id),
+            Some(0)
+        );
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+        let result = catch_unwind(AssertUnwindSafe(move || std::mem::drop(list)));
+        assert!(result.is_err());
+        // every node that was still linked gets dropped exactly once, either before the panic or
+        // by a later reclamation pass
+        HazPtrHolder::try_reclaim();
+        assert_eq!(DROPS.load(Ordering::SeqCst), 5);
+    }
+
+    // A predicate that panics must not leave its delete in the way of everybody else.
+    #[test]
+    fn panic_in_delete_predicate() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let list = LinkedList::new();
+        list.insert_from_head(0).unwrap();
+        list.insert_from_head(1).unwrap();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.delete_from_tail_if(|_| panic!("predicate went off"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(list.delete_from_tail(), Ok(Some(0)));
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.delete_from_tail_if(|_| panic!("predicate went off"))
+        }));
+        assert!(result.is_err());
+        list.insert_from_head(2).unwrap();
+        assert_eq!(list.delete_many_from_tail(2), Ok(vec![1, 2]));
+    }
+
+    // Neither must one that panics while a remove holds on to the value.
+    #[test]
+    fn panic_in_remove_predicate() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let list = LinkedList::new();
+        list.insert_batch_from_head(0..3).unwrap();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.remove_first(|&value| value == 2 || panic!("predicate went off"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(list.delete_from_tail(), Ok(Some(0)));
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            list.retain(|_| panic!("predicate went off"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(list.remove_first(|&value| value == 2), Some(2));
+        assert_eq!(list.delete_many_from_tail(3), Ok(vec![1]));
+    }
+}
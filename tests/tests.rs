@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod queue_test {
     use ruby::list::LinkedList;
+    use ruby::{Backoff, Exponential, NoBackoff, Spin};
     use std::time::Instant;
     #[test]
     fn test_one() {
@@ -9,7 +10,7 @@ mod queue_test {
         std::thread::scope(|s| {
             for i in 0..10 {
                 s.spawn(move || {
-                    new.insert_from_head(i);
+                    new.insert_from_head(i).unwrap();
                 });
             }
         });
@@ -20,8 +21,738 @@ mod queue_test {
                 });
             }
         });
-        assert_eq!(0 as usize, new.length());
+        assert_eq!(0_usize, new.len());
         let time_taken = current.elapsed();
         println!("{:?}", time_taken.as_micros());
     }
+
+    #[test]
+    fn test_pooled() {
+        let new = &LinkedList::with_pool();
+        for round in 0..10 {
+            std::thread::scope(|s| {
+                for i in 0..10 {
+                    s.spawn(move || {
+                        new.insert_from_head(round * 10 + i).unwrap();
+                    });
+                }
+            });
+            let taken = std::sync::Mutex::new(Vec::new());
+            std::thread::scope(|s| {
+                for _ in 0..10 {
+                    s.spawn(|| {
+                        if let Ok(Some(value)) = new.delete_from_tail() {
+                            taken.lock().unwrap().push(value);
+                        }
+                    });
+                }
+            });
+            let mut taken = taken.into_inner().unwrap();
+            taken.sort();
+            assert_eq!(taken, (round * 10..round * 10 + 10).collect::<Vec<_>>());
+            assert_eq!(0_usize, new.len());
+        }
+    }
+
+    #[test]
+    fn test_both_ends() {
+        // producers and consumers run at the same time, so most deletes happen while inserts are
+        // going on at the other end and the list keeps running empty in between. The wait-free
+        // list has to hold up the same way with every operation going through the announcements
+        const PRODUCERS: usize = 4;
+        const PER_PRODUCER: usize = 2000;
+        for new in [LinkedList::new(), LinkedList::new().wait_free()] {
+            let new = &new;
+            let remaining = &std::sync::atomic::AtomicUsize::new(PRODUCERS * PER_PRODUCER);
+            let taken = std::thread::scope(|s| {
+                for p in 0..PRODUCERS {
+                    s.spawn(move || {
+                        for i in 0..PER_PRODUCER {
+                            new.insert_from_head(p * PER_PRODUCER + i).unwrap();
+                        }
+                    });
+                }
+                let consumers: Vec<_> = (0..PRODUCERS)
+                    .map(|_| {
+                        s.spawn(move || {
+                            let mut taken = Vec::new();
+                            while remaining.load(std::sync::atomic::Ordering::SeqCst) > 0 {
+                                if let Ok(Some(value)) = new.delete_from_tail() {
+                                    remaining.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                                    taken.push(value);
+                                }
+                            }
+                            taken
+                        })
+                    })
+                    .collect();
+                consumers
+                    .into_iter()
+                    .map(|consumer| consumer.join().unwrap())
+                    .collect::<Vec<_>>()
+            });
+            // every consumer has to see the values of a single producer in the order they went in
+            for values in taken.iter() {
+                for p in 0..PRODUCERS {
+                    let from_producer: Vec<_> = values
+                        .iter()
+                        .filter(|value| **value / PER_PRODUCER == p)
+                        .collect();
+                    assert!(from_producer.windows(2).all(|pair| pair[0] < pair[1]));
+                }
+            }
+            let mut taken = taken.concat();
+            taken.sort();
+            assert_eq!(taken, (0..PRODUCERS * PER_PRODUCER).collect::<Vec<_>>());
+            assert_eq!(0_usize, new.len());
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        static NO_BACKOFF: NoBackoff = NoBackoff::new();
+        static SPIN: Spin = Spin::new();
+        static EXPONENTIAL: Exponential = Exponential::new();
+        let strategies: [&'static dyn Backoff; 3] = [&NO_BACKOFF, &SPIN, &EXPONENTIAL];
+        for strategy in strategies {
+            let new = &LinkedList::new().with_backoff(strategy);
+            std::thread::scope(|s| {
+                for t in 0..4 {
+                    s.spawn(move || {
+                        for i in 0..500 {
+                            new.insert_from_head(t * 500 + i).unwrap();
+                        }
+                    });
+                    s.spawn(move || {
+                        for _ in 0..500 {
+                            let _ = new.delete_from_tail();
+                        }
+                    });
+                }
+            });
+            while new.delete_from_tail().unwrap().is_some() {}
+            assert_eq!(0_usize, new.len());
+        }
+    }
+
+    #[test]
+    fn test_len() {
+        // every worker deletes only after its own insert returned, so the one value that was in
+        // the list from the start keeps it from ever being empty while the workers run
+        const WORKERS: usize = 4;
+        let new = &LinkedList::new();
+        new.insert_from_head(usize::MAX).unwrap();
+        let running = &std::sync::atomic::AtomicUsize::new(WORKERS);
+        std::thread::scope(|s| {
+            for t in 0..WORKERS {
+                s.spawn(move || {
+                    for i in 0..2000 {
+                        new.insert_from_head(t * 2000 + i).unwrap();
+                        let _ = new.delete_from_tail();
+                    }
+                    running.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                });
+            }
+            s.spawn(move || {
+                while running.load(std::sync::atomic::Ordering::SeqCst) > 0 {
+                    let len = new.len();
+                    assert!((1..=WORKERS + 1).contains(&len), "{len}");
+                    assert!(!new.is_empty());
+                }
+            });
+        });
+        assert_eq!(new.len(), 1);
+        assert_eq!(new.approx_len(), 1);
+        let _ = new.delete_from_tail();
+        assert!(new.is_empty());
+    }
+
+    #[test]
+    fn test_capacity() {
+        let new = &LinkedList::with_capacity_limit(8);
+        for i in 0..8 {
+            assert_eq!(new.try_insert_from_head(i), Ok(()));
+        }
+        assert_eq!(new.try_insert_from_head(8), Err(8));
+        assert_eq!(new.len(), 8);
+        assert_eq!(new.delete_from_tail(), Ok(Some(0)));
+        assert_eq!(new.try_insert_from_head(8), Ok(()));
+        while new.delete_from_tail().unwrap().is_some() {}
+        // nothing ever fits into a list without room, the insert must not wait for a delete
+        let empty = LinkedList::with_capacity_limit(0);
+        assert_eq!(empty.insert_from_head(0), Err(0));
+        assert_eq!(empty.insert_from_head_with_handle(1).err(), Some(1));
+        assert!(empty.is_empty());
+        // producers race for the last free places while consumers keep making new ones, the list
+        // must never be seen holding more than its limit
+        let running = &std::sync::atomic::AtomicUsize::new(4);
+        let accepted = &std::sync::atomic::AtomicUsize::new(0);
+        let deleted = &std::sync::atomic::AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                s.spawn(move || {
+                    for i in 0..2000 {
+                        if new.try_insert_from_head(t * 2000 + i).is_ok() {
+                            accepted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        }
+                    }
+                    running.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                });
+            }
+            for _ in 0..2 {
+                s.spawn(move || {
+                    while running.load(std::sync::atomic::Ordering::SeqCst) > 0 {
+                        if new.delete_from_tail().unwrap().is_some() {
+                            deleted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        }
+                        assert!(new.len() <= 8);
+                    }
+                });
+            }
+        });
+        let accepted = accepted.load(std::sync::atomic::Ordering::SeqCst);
+        let deleted = deleted.load(std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(new.len(), accepted - deleted);
+        assert!(new.len() <= 8);
+    }
+
+    #[test]
+    fn test_capacity_waits() {
+        // inserts that find the list full wait for the consumer instead of handing values back,
+        // yielding once that takes a while so the consumer gets to run
+        static EXPONENTIAL: Exponential = Exponential::new();
+        let new = &LinkedList::with_capacity_limit(4).with_backoff(&EXPONENTIAL);
+        let taken = std::thread::scope(|s| {
+            for t in 0..4 {
+                s.spawn(move || {
+                    for i in 0..500 {
+                        new.insert_from_head(t * 500 + i).unwrap();
+                    }
+                });
+            }
+            s.spawn(move || {
+                let mut taken = Vec::new();
+                while taken.len() < 2000 {
+                    if let Ok(Some(value)) = new.delete_from_tail() {
+                        taken.push(value);
+                    }
+                }
+                taken
+            })
+            .join()
+            .unwrap()
+        });
+        let mut taken = taken;
+        taken.sort();
+        assert_eq!(taken, (0..2000).collect::<Vec<_>>());
+        assert!(new.is_empty());
+    }
+
+    #[test]
+    fn test_insert_batch() {
+        let new = &LinkedList::new();
+        new.insert_batch_from_head(0..4).unwrap();
+        new.insert_batch_from_head(Vec::new()).unwrap();
+        new.insert_batch_from_head(4..6).unwrap();
+        assert_eq!(new.len(), 6);
+        for i in 0..6 {
+            assert_eq!(new.delete_from_tail(), Ok(Some(i)));
+        }
+        // every batch has to come out in one piece, no matter what the other producers and the
+        // consumer do at the same time
+        const BATCH: usize = 10;
+        let taken = std::thread::scope(|s| {
+            for t in 0..4 {
+                s.spawn(move || {
+                    for b in 0..50 {
+                        let start = (t * 50 + b) * BATCH;
+                        new.insert_batch_from_head(start..start + BATCH).unwrap();
+                    }
+                });
+            }
+            s.spawn(move || {
+                let mut taken = Vec::new();
+                while taken.len() < 4 * 50 * BATCH {
+                    if let Ok(Some(value)) = new.delete_from_tail() {
+                        taken.push(value);
+                    }
+                }
+                taken
+            })
+            .join()
+            .unwrap()
+        });
+        for batch in taken.chunks(BATCH) {
+            assert_eq!(batch[0] % BATCH, 0);
+            assert_eq!(batch, (batch[0]..batch[0] + BATCH).collect::<Vec<_>>());
+        }
+        assert!(new.is_empty());
+    }
+
+    #[test]
+    fn test_insert_batch_capacity() {
+        let new = &LinkedList::with_capacity_limit(4);
+        assert_eq!(new.insert_batch_from_head(0..5), Err((0..5).collect()));
+        new.insert_batch_from_head(0..3).unwrap();
+        std::thread::scope(|s| {
+            let producer = s.spawn(move || new.insert_batch_from_head(3..5));
+            // the batch only goes in once there is room for all of it
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            assert_eq!(new.len(), 3);
+            assert_eq!(new.delete_from_tail(), Ok(Some(0)));
+            producer.join().unwrap().unwrap();
+        });
+        assert_eq!(new.len(), 4);
+        new.close();
+        assert_eq!(new.insert_batch_from_head(5..7), Err(vec![5, 6]));
+        for i in 1..5 {
+            assert_eq!(new.delete_from_tail(), Ok(Some(i)));
+        }
+    }
+
+    #[test]
+    fn test_delete_many() {
+        let new = &LinkedList::new();
+        assert_eq!(new.delete_many_from_tail(3), Ok(Vec::new()));
+        new.insert_batch_from_head(0..10).unwrap();
+        assert_eq!(new.delete_many_from_tail(4), Ok(vec![0, 1, 2, 3]));
+        assert_eq!(new.len(), 6);
+        let mut buffer = vec![100];
+        // the batch stops short of the last node, which still comes out in the same call
+        assert_eq!(new.delete_many_into(10, &mut buffer), Ok(6));
+        assert_eq!(buffer, vec![100, 4, 5, 6, 7, 8, 9]);
+        assert!(new.is_empty());
+        new.insert_from_head(10).unwrap();
+        new.close();
+        assert_eq!(new.delete_many_from_tail(2), Ok(vec![10]));
+        assert_eq!(new.delete_many_from_tail(2), Err(ruby::Closed));
+        // consumers taking batches while producers keep going still see every value once and the
+        // values of every producer in order
+        const PER_PRODUCER: usize = 1000;
+        let new = &LinkedList::new();
+        let total = &std::sync::atomic::AtomicUsize::new(0);
+        let taken = std::thread::scope(|s| {
+            for t in 0..2 {
+                s.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        new.insert_from_head(t * PER_PRODUCER + i).unwrap();
+                    }
+                });
+            }
+            let consumers: Vec<_> = (0..2)
+                .map(|_| {
+                    s.spawn(move || {
+                        let mut taken: Vec<usize> = Vec::new();
+                        while total.load(std::sync::atomic::Ordering::SeqCst) < 2 * PER_PRODUCER {
+                            let got = new.delete_many_into(7, &mut taken).unwrap();
+                            total.fetch_add(got, std::sync::atomic::Ordering::SeqCst);
+                        }
+                        for t in 0..2 {
+                            let own: Vec<_> = taken
+                                .iter()
+                                .filter(|&&value| value / PER_PRODUCER == t)
+                                .collect();
+                            assert!(own.windows(2).all(|pair| pair[0] < pair[1]));
+                        }
+                        taken
+                    })
+                })
+                .collect();
+            consumers
+                .into_iter()
+                .map(|consumer| consumer.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        let mut taken = taken.concat();
+        taken.sort();
+        assert_eq!(taken, (0..2 * PER_PRODUCER).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_take_all() {
+        let new = &LinkedList::new();
+        assert_eq!(new.take_all().count(), 0);
+        new.insert_batch_from_head(0..5).unwrap();
+        let drained = new.take_all();
+        assert!(new.is_empty());
+        assert_eq!(new.delete_from_tail(), Ok(None));
+        new.insert_from_head(5).unwrap();
+        assert_eq!(drained.collect::<Vec<_>>(), (0..5).collect::<Vec<_>>());
+        assert_eq!(new.len(), 1);
+        // nothing is lost or handed out twice while producers and a consumer keep going, and the
+        // values of every producer come out of every drain in order
+        const PER_PRODUCER: usize = 2000;
+        let new = &LinkedList::new();
+        let drained = std::thread::scope(|s| {
+            let producers: Vec<_> = (0..2)
+                .map(|t| {
+                    s.spawn(move || {
+                        for i in 0..PER_PRODUCER {
+                            new.insert_from_head(t * PER_PRODUCER + i).unwrap();
+                        }
+                    })
+                })
+                .collect();
+            let consumer = s.spawn(move || {
+                let mut taken = Vec::new();
+                for _ in 0..PER_PRODUCER {
+                    if let Ok(Some(value)) = new.delete_from_tail() {
+                        taken.push(value);
+                    }
+                }
+                taken
+            });
+            let mut drains = Vec::new();
+            while producers.iter().any(|producer| !producer.is_finished()) {
+                drains.push(new.take_all().collect::<Vec<_>>());
+            }
+            drains.push(consumer.join().unwrap());
+            drains
+        });
+        for drain in &drained[..drained.len() - 1] {
+            for t in 0..2 {
+                let own: Vec<_> = drain
+                    .iter()
+                    .filter(|&&value| value / PER_PRODUCER == t)
+                    .collect();
+                assert!(own.windows(2).all(|pair| pair[0] < pair[1]));
+            }
+        }
+        let mut taken = drained.concat();
+        taken.extend(new.take_all());
+        taken.sort();
+        assert_eq!(taken, (0..2 * PER_PRODUCER).collect::<Vec<_>>());
+        assert!(new.is_empty());
+    }
+
+    #[test]
+    fn test_append() {
+        let global = &LinkedList::new();
+        let worker = LinkedList::new();
+        global.append(&worker).unwrap();
+        assert!(global.is_empty());
+        global.insert_from_head(0).unwrap();
+        worker.insert_batch_from_head(1..4).unwrap();
+        global.append(&worker).unwrap();
+        assert!(worker.is_empty());
+        assert_eq!(global.len(), 4);
+        worker.insert_from_head(4).unwrap();
+        global.close();
+        assert_eq!(global.append(&worker), Err(Vec::new()));
+        assert_eq!(worker.len(), 1);
+        // nothing is lost when this list is too small either
+        let small = LinkedList::with_capacity_limit(1);
+        let pair = LinkedList::new();
+        pair.insert_batch_from_head(1..3).unwrap();
+        assert_eq!(small.append(&pair), Err(Vec::new()));
+        assert_eq!(pair.take_all().collect::<Vec<_>>(), vec![1, 2]);
+        assert!(small.is_empty());
+        // or when it has no room next to what it holds already, and the append does not wait
+        let three = LinkedList::with_capacity_limit(3);
+        three.insert_batch_from_head(0..2).unwrap();
+        pair.insert_batch_from_head(1..3).unwrap();
+        assert_eq!(three.append(&pair), Err(Vec::new()));
+        assert_eq!(pair.len(), 2);
+        assert_eq!(three.len(), 2);
+        three.delete_from_tail().unwrap();
+        three.append(&pair).unwrap();
+        assert!(pair.is_empty());
+        assert_eq!(three.take_all().collect::<Vec<_>>(), vec![1, 1, 2]);
+        assert_eq!(
+            global.take_all().collect::<Vec<_>>(),
+            (0..4).collect::<Vec<_>>()
+        );
+        // workers merging their lists into a global one while a consumer takes from it, every
+        // value turns up once and the values of a worker stay in order
+        const PER_WORKER: usize = 1000;
+        let global = &LinkedList::new();
+        let taken = std::thread::scope(|s| {
+            for t in 0..2 {
+                s.spawn(move || {
+                    let local = LinkedList::new();
+                    for i in 0..PER_WORKER {
+                        local.insert_from_head(t * PER_WORKER + i).unwrap();
+                        if i % 10 == 9 {
+                            global.append(&local).unwrap();
+                        }
+                    }
+                });
+            }
+            s.spawn(move || {
+                let mut taken = Vec::new();
+                while taken.len() < 2 * PER_WORKER {
+                    if let Ok(Some(value)) = global.delete_from_tail() {
+                        taken.push(value);
+                    }
+                }
+                taken
+            })
+            .join()
+            .unwrap()
+        });
+        for t in 0..2 {
+            let own: Vec<_> = taken
+                .iter()
+                .filter(|&&value| value / PER_WORKER == t)
+                .collect();
+            assert!(own.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        assert!(global.is_empty());
+    }
+
+    #[test]
+    fn test_delete_if() {
+        use ruby::DeleteIfError;
+        let new = &LinkedList::new();
+        assert_eq!(new.delete_from_tail_if(|_| true), Err(DeleteIfError::Empty));
+        new.insert_batch_from_head(0..4).unwrap();
+        assert_eq!(new.delete_from_tail_if(|&value| value == 0), Ok(0));
+        assert_eq!(
+            new.delete_from_tail_if(|&value| value == 0),
+            Err(DeleteIfError::Rejected)
+        );
+        assert_eq!(new.len(), 3);
+        assert_eq!(new.delete_many_from_tail(2), Ok(vec![1, 2]));
+        // the last value goes through the head descriptor
+        assert_eq!(
+            new.delete_from_tail_if(|&value| value == 0),
+            Err(DeleteIfError::Rejected)
+        );
+        assert_eq!(new.delete_from_tail_if(|&value| value == 3), Ok(3));
+        new.close();
+        assert_eq!(
+            new.delete_from_tail_if(|_| true),
+            Err(DeleteIfError::Closed)
+        );
+        // a conditional consumer next to a plain one and a producer, the conditional one only
+        // ever takes even values and between the two every value comes out once, in order
+        const VALUES: usize = 2000;
+        let new = &LinkedList::new();
+        let total = &std::sync::atomic::AtomicUsize::new(0);
+        let (even, rest) = std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..VALUES {
+                    new.insert_from_head(i).unwrap();
+                }
+            });
+            let conditional = s.spawn(move || {
+                let mut taken = Vec::new();
+                while total.load(std::sync::atomic::Ordering::SeqCst) < VALUES {
+                    if let Ok(value) = new.delete_from_tail_if(|&value| value % 2 == 0) {
+                        taken.push(value);
+                        total.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    }
+                }
+                taken
+            });
+            let mut rest = Vec::new();
+            while total.load(std::sync::atomic::Ordering::SeqCst) < VALUES {
+                if let Ok(Some(value)) = new.delete_from_tail() {
+                    rest.push(value);
+                    total.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+            }
+            (conditional.join().unwrap(), rest)
+        });
+        assert!(even.iter().all(|value| value % 2 == 0));
+        assert!(even.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(rest.windows(2).all(|pair| pair[0] < pair[1]));
+        let mut taken = [even, rest].concat();
+        taken.sort();
+        assert_eq!(taken, (0..VALUES).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_remove_first() {
+        let new = &LinkedList::new();
+        assert_eq!(new.remove_first(|_| true), None);
+        new.insert_batch_from_head(0..6).unwrap();
+        assert_eq!(new.remove_first(|&value| value % 2 == 1), Some(1));
+        assert_eq!(new.remove_first(|&value| value == 1), None);
+        // the walk unlinks the node right away as it is in the middle, and it is no longer counted
+        assert_eq!(new.len(), 5);
+        assert_eq!(new.approx_len(), 5);
+        assert_eq!(new.delete_from_tail(), Ok(Some(0)));
+        assert_eq!(new.delete_from_tail(), Ok(Some(2)));
+        assert_eq!(new.len(), 3);
+        assert_eq!(new.approx_len(), 3);
+        assert_eq!(new.remove_first(|&value| value == 5), Some(5));
+        assert_eq!(new.delete_many_from_tail(3), Ok(vec![3, 4]));
+        assert!(new.is_empty());
+        assert_eq!(new.approx_len(), 0);
+        new.insert_batch_from_head(0..3).unwrap();
+        assert_eq!(new.remove_first(|&value| value == 1), Some(1));
+        assert_eq!(new.delete_from_tail_if(|&value| value == 0), Ok(0));
+        assert_eq!(new.delete_from_tail_if(|&value| value == 2), Ok(2));
+        new.insert_batch_from_head(0..3).unwrap();
+        assert_eq!(new.remove_first(|&value| value == 0), Some(0));
+        assert_eq!(new.take_all().collect::<Vec<_>>(), vec![1, 2]);
+        let pair = LinkedList::new();
+        pair.insert_batch_from_head(0..2).unwrap();
+        assert_eq!(pair.remove_first(|_| true), Some(0));
+        assert_eq!(pair.len(), 1);
+        pair.retain(|_| false);
+        assert!(pair.is_empty());
+        assert_eq!(pair.approx_len(), 0);
+        // the room a remove makes can be used right away
+        let full = LinkedList::with_capacity_limit(2);
+        full.insert_batch_from_head(0..2).unwrap();
+        assert_eq!(full.try_insert_from_head(2), Err(2));
+        full.retain(|_| false);
+        full.try_insert_from_head(2).unwrap();
+        full.try_insert_from_head(3).unwrap();
+        assert_eq!(full.try_insert_from_head(4), Err(4));
+        assert_eq!(full.len(), 2);
+        assert_eq!(full.take_all().collect::<Vec<_>>(), vec![2, 3]);
+        // a list that only ever holds one value at a time does not pile up removed nodes
+        let single = LinkedList::with_capacity_limit(1);
+        for i in 0..10_000 {
+            single.try_insert_from_head(i).unwrap();
+            assert_eq!(single.remove_first(|_| true), Some(i));
+        }
+        assert!(single.is_empty());
+        single.try_insert_from_head(0).unwrap();
+        assert_eq!(single.try_insert_from_head(1), Err(1));
+        assert_eq!(single.delete_from_tail(), Ok(Some(0)));
+        assert_eq!(single.delete_from_tail(), Ok(None));
+        // removes next to a producer and a consumer, every value comes out exactly once
+        const VALUES: usize = 2000;
+        let new = &LinkedList::new();
+        let total = &std::sync::atomic::AtomicUsize::new(0);
+        let (removed, rest) = std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..VALUES {
+                    new.insert_from_head(i).unwrap();
+                }
+            });
+            let remover = s.spawn(move || {
+                let mut taken = Vec::new();
+                while total.load(std::sync::atomic::Ordering::SeqCst) < VALUES {
+                    if let Some(value) = new.remove_first(|&value| value % 3 == 0) {
+                        taken.push(value);
+                        total.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    }
+                }
+                taken
+            });
+            let mut rest = Vec::new();
+            while total.load(std::sync::atomic::Ordering::SeqCst) < VALUES {
+                if let Ok(Some(value)) = new.delete_from_tail() {
+                    rest.push(value);
+                    total.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+            }
+            (remover.join().unwrap(), rest)
+        });
+        assert!(removed.iter().all(|value| value % 3 == 0));
+        assert!(rest.windows(2).all(|pair| pair[0] < pair[1]));
+        let mut taken = [removed, rest].concat();
+        taken.sort();
+        assert_eq!(taken, (0..VALUES).collect::<Vec<_>>());
+        assert!(new.is_empty());
+        assert_eq!(new.approx_len(), 0);
+    }
+
+    #[test]
+    fn test_retain() {
+        let new = &LinkedList::new();
+        new.retain(|_| false);
+        new.insert_batch_from_head(0..10).unwrap();
+        new.retain(|&value| value % 2 == 0);
+        assert_eq!(new.remove_first(|&value| value % 2 == 1), None);
+        assert_eq!(
+            new.delete_many_from_tail(10),
+            Ok((0..10).step_by(2).collect::<Vec<_>>())
+        );
+        // the removed values are dropped right away
+        let counter = std::sync::Arc::new(());
+        let new = LinkedList::new();
+        for _ in 0..4 {
+            new.insert_from_head(counter.clone()).unwrap();
+        }
+        let mut keep = false;
+        new.retain(|_| {
+            keep = !keep;
+            keep
+        });
+        assert_eq!(std::sync::Arc::strong_count(&counter), 3);
+        drop(new);
+        assert_eq!(std::sync::Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_entry_handle() {
+        let new = &LinkedList::new();
+        let first = new.insert_from_head_with_handle(0).unwrap();
+        let second = new.insert_from_head_with_handle(1).unwrap();
+        let third = new.insert_from_head_with_handle(2).unwrap();
+        assert!(second.is_pending());
+        assert_eq!(second.try_cancel(), Some(1));
+        // the cancelled node sat in the middle and was unlinked right away
+        assert_eq!(new.delete_from_tail(), Ok(Some(0)));
+        assert!(!first.is_pending());
+        assert_eq!(first.try_cancel(), None);
+        assert_eq!(new.delete_from_tail(), Ok(Some(2)));
+        assert_eq!(third.try_cancel(), None);
+        assert!(new.is_empty());
+        assert_eq!(new.approx_len(), 0);
+        // a value cancelled after it was inserted into a full list is handed back as well
+        let new = &LinkedList::with_capacity_limit(1);
+        let handle = new.insert_from_head_with_handle(3).unwrap();
+        assert_eq!(new.try_insert_from_head(4), Err(4));
+        assert_eq!(handle.try_cancel(), Some(3));
+        // and makes room for the next one right away
+        assert!(new.is_empty());
+        new.try_insert_from_head(4).unwrap();
+        assert_eq!(new.len(), 1);
+        assert_eq!(new.delete_from_tail(), Ok(Some(4)));
+        new.close();
+        assert_eq!(new.insert_from_head_with_handle(5).err(), Some(5));
+        // cancelled nodes do not pile up behind a value that stays
+        let kept = LinkedList::with_capacity_limit(2);
+        kept.insert_from_head(0).unwrap();
+        for i in 1..10_000 {
+            let handle = kept.insert_from_head_with_handle(i).unwrap();
+            assert_eq!(handle.try_cancel(), Some(i));
+        }
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept.delete_from_tail(), Ok(Some(0)));
+        assert_eq!(kept.delete_from_tail(), Ok(None));
+        assert_eq!(new.delete_from_tail(), Err(ruby::Closed));
+        // cancels next to a consumer, every value comes out exactly once
+        const VALUES: usize = 2000;
+        let new = &LinkedList::new();
+        let (cancelled, consumed) = std::thread::scope(|s| {
+            let producer = s.spawn(move || {
+                let mut cancelled = Vec::new();
+                let mut handles = std::collections::VecDeque::new();
+                for i in 0..VALUES {
+                    handles.push_back(new.insert_from_head_with_handle(i).unwrap());
+                    // every other handle tries to cancel, the rest are left to the consumer
+                    if handles.len() > 4 {
+                        let handle = handles.pop_front().unwrap();
+                        if i % 2 == 1 {
+                            cancelled.extend(handle.try_cancel());
+                        }
+                    }
+                }
+                cancelled.extend(handles.into_iter().filter_map(|handle| handle.try_cancel()));
+                new.close();
+                cancelled
+            });
+            let mut consumed = Vec::new();
+            loop {
+                match new.delete_from_tail() {
+                    Ok(Some(value)) => consumed.push(value),
+                    Ok(None) => std::thread::yield_now(),
+                    Err(_) => break,
+                }
+            }
+            (producer.join().unwrap(), consumed)
+        });
+        assert!(consumed.windows(2).all(|pair| pair[0] < pair[1]));
+        let mut taken = [cancelled, consumed].concat();
+        taken.sort();
+        assert_eq!(taken, (0..VALUES).collect::<Vec<_>>());
+    }
 }
@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod wait_free_test {
+    use ruby::list::LinkedList;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    // A single thread works against a crowd that never lets up on either end of the list and only
+    // stops once that thread is done. Every operation of the crowd helps the older announcements
+    // first, so the lone thread can not be starved by threads that keep winning the CAS, however
+    // the scheduler interleaves them. Without that guarantee this test may never finish.
+    #[test]
+    fn test_no_starvation() {
+        const ADVERSARIES: usize = 8;
+        let new = &LinkedList::new().wait_free();
+        let stop = &AtomicBool::new(false);
+        let adversary_ops = &AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for a in 0..ADVERSARIES {
+                s.spawn(move || {
+                    let mut i = 0;
+                    while !stop.load(Ordering::SeqCst) {
+                        if a % 2 == 0 {
+                            new.insert_from_head(usize::MAX).unwrap();
+                        } else {
+                            let _ = new.delete_from_tail();
+                        }
+                        i += 1;
+                    }
+                    adversary_ops.fetch_add(i, Ordering::SeqCst);
+                });
+            }
+            s.spawn(move || {
+                for i in 0..1000 {
+                    new.insert_from_head(i).unwrap();
+                    let _ = new.delete_from_tail();
+                }
+                stop.store(true, Ordering::SeqCst);
+            });
+        });
+        assert!(adversary_ops.load(Ordering::SeqCst) > 0);
+        while new.delete_from_tail().unwrap().is_some() {}
+        assert_eq!(0_usize, new.len());
+    }
+
+    #[test]
+    fn test_drop_with_values() {
+        let new = LinkedList::new().wait_free();
+        for i in 0..10 {
+            new.insert_from_head(vec![i]).unwrap();
+        }
+        assert_eq!(new.delete_from_tail(), Ok(Some(vec![0])));
+        assert_eq!(new.len(), 9);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let new = &LinkedList::with_capacity_limit(4).wait_free();
+        for i in 0..4 {
+            assert_eq!(new.try_insert_from_head(i), Ok(()));
+        }
+        assert_eq!(new.try_insert_from_head(4), Err(4));
+        let accepted = &AtomicUsize::new(0);
+        let deleted = &AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        if new.try_insert_from_head(t * 1000 + i).is_ok() {
+                            accepted.fetch_add(1, Ordering::SeqCst);
+                        }
+                        if new.delete_from_tail().unwrap().is_some() {
+                            deleted.fetch_add(1, Ordering::SeqCst);
+                        }
+                        assert!(new.len() <= 4);
+                    }
+                });
+            }
+        });
+        let accepted = accepted.load(Ordering::SeqCst) + 4;
+        assert_eq!(new.len(), accepted - deleted.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_insert_batch() {
+        let new = LinkedList::new().wait_free();
+        new.insert_batch_from_head(0..4).unwrap();
+        assert_eq!(new.len(), 4);
+        new.close();
+        assert_eq!(new.insert_batch_from_head(4..6), Err(vec![4, 5]));
+        for i in 0..4 {
+            assert_eq!(new.delete_from_tail(), Ok(Some(i)));
+        }
+        // the announced inserts never land in the middle of a batch
+        const BATCH: usize = 10;
+        let new = &LinkedList::new().wait_free();
+        std::thread::scope(|s| {
+            for t in 0..2 {
+                s.spawn(move || {
+                    for b in 0..50 {
+                        let start = (t * 50 + b) * BATCH;
+                        new.insert_batch_from_head(start..start + BATCH).unwrap();
+                    }
+                });
+            }
+            s.spawn(move || {
+                for i in 0..500 {
+                    new.insert_from_head(1000 + i).unwrap();
+                }
+            });
+        });
+        let mut taken = Vec::new();
+        while let Ok(Some(value)) = new.delete_from_tail() {
+            if value < 1000 {
+                taken.push(value);
+            } else {
+                assert_eq!(taken.len() % BATCH, 0);
+            }
+        }
+        assert_eq!(taken.len(), 1000);
+        for batch in taken.chunks(BATCH) {
+            assert_eq!(batch, (batch[0]..batch[0] + BATCH).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_delete_many() {
+        let new = LinkedList::new().wait_free();
+        new.insert_batch_from_head(0..5).unwrap();
+        assert_eq!(new.delete_many_from_tail(3), Ok(vec![0, 1, 2]));
+        let mut buffer = Vec::new();
+        assert_eq!(new.delete_many_into(3, &mut buffer), Ok(2));
+        assert_eq!(buffer, vec![3, 4]);
+        new.close();
+        assert!(new.delete_many_from_tail(1).is_err());
+    }
+
+    #[test]
+    fn test_take_all() {
+        let new = LinkedList::new().wait_free();
+        new.insert_batch_from_head(0..3).unwrap();
+        assert_eq!(new.take_all().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert!(new.is_empty());
+        new.insert_from_head(3).unwrap();
+        assert_eq!(new.delete_from_tail(), Ok(Some(3)));
+    }
+
+    #[test]
+    fn test_remove_first() {
+        let new = LinkedList::new().wait_free();
+        new.insert_batch_from_head(0..4).unwrap();
+        assert_eq!(new.remove_first(|&value| value < 2), Some(0));
+        new.retain(|&value| value != 2);
+        // the announced deletes go past the nodes that lost their values
+        assert_eq!(new.delete_from_tail(), Ok(Some(1)));
+        assert_eq!(new.delete_from_tail(), Ok(Some(3)));
+        assert_eq!(new.delete_from_tail(), Ok(None));
+        assert_eq!(new.approx_len(), 0);
+    }
+
+    #[test]
+    fn test_entry_handle() {
+        let new = LinkedList::new().wait_free();
+        let first = new.insert_from_head_with_handle(0).unwrap();
+        let second = new.insert_from_head_with_handle(1).unwrap();
+        assert_eq!(first.try_cancel(), Some(0));
+        assert_eq!(new.delete_from_tail(), Ok(Some(1)));
+        assert_eq!(second.try_cancel(), None);
+        assert_eq!(new.delete_from_tail(), Ok(None));
+    }
+}